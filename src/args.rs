@@ -1,5 +1,12 @@
+use crate::format::Format;
+use crate::render::Plot;
+use crate::syntax::{self, ParseError};
 use crate::{cratename, user};
-use clap::builder::{ArgAction, ValueParser};
+use cargo_tally::dependency::{DependencyKind, DependencyKinds, Optional};
+use cargo_tally::timestamp::{DateTime, Interval};
+use chrono::{NaiveDate, NaiveTime};
+use clap::builder::{ArgAction, PossibleValuesParser, TypedValueParser, ValueParser};
+use clap::error::ErrorKind;
 use clap::{Arg, Command};
 use regex::Regex;
 use semver::VersionReq;
@@ -11,18 +18,42 @@ use thiserror::Error;
 
 #[derive(Debug)]
 pub(crate) struct Opt {
+    pub at: Option<DateTime>,
+    pub by_default_features: bool,
+    pub by_major: bool,
+    pub by_version: bool,
+    pub cdn: bool,
+    pub change: Option<u32>,
     pub db: PathBuf,
+    pub dependencies_of: bool,
+    pub depth: Vec<u16>,
+    pub diff: Option<(DateTime, DateTime)>,
     pub exclude: Vec<Regex>,
+    pub explain: Option<String>,
+    pub format: Option<Format>,
+    pub interval: Option<Interval>,
     pub jobs: usize,
+    pub kind: Option<DependencyKinds>,
+    pub no_open: bool,
+    pub optional: Optional,
+    pub output: Option<PathBuf>,
+    pub plot: Plot,
     pub relative: bool,
+    pub since: Option<DateTime>,
     pub title: Option<String>,
+    pub top: Option<usize>,
     pub transitive: bool,
+    pub trending: bool,
+    pub until: Option<DateTime>,
     pub queries: Vec<String>,
 }
 
 const USAGE: &str = "\
     cargo tally [OPTIONS] QUERIES...
-    cargo tally serde:1.0 'anyhow:^1.0 + thiserror'";
+    cargo tally serde:1.0 'anyhow:^1.0 + thiserror'
+    cargo tally --dependencies-of tokio
+    cargo tally --top 100
+    cargo tally --trending --change 6";
 
 const TEMPLATE: &str = "\
 {bin} {version}
@@ -39,12 +70,33 @@ fn app(jobs_help: &String) -> Command {
     let mut app = Command::new("cargo-tally")
         .override_usage(USAGE)
         .help_template(TEMPLATE)
+        .arg(arg_at())
+        .arg(arg_by_default_features())
+        .arg(arg_by_major())
+        .arg(arg_by_version())
+        .arg(arg_cdn())
+        .arg(arg_change())
         .arg(arg_db())
+        .arg(arg_dependencies_of())
+        .arg(arg_depth())
+        .arg(arg_diff())
         .arg(arg_exclude())
+        .arg(arg_explain())
+        .arg(arg_format())
+        .arg(arg_interval())
         .arg(arg_jobs(jobs_help))
+        .arg(arg_kind())
+        .arg(arg_no_open())
+        .arg(arg_optional())
+        .arg(arg_output())
+        .arg(arg_plot())
         .arg(arg_relative())
+        .arg(arg_since())
         .arg(arg_title())
+        .arg(arg_top())
         .arg(arg_transitive())
+        .arg(arg_trending())
+        .arg(arg_until())
         .arg(arg_queries());
     if let Some(version) = option_env!("CARGO_PKG_VERSION") {
         app = app.version(version);
@@ -52,15 +104,40 @@ fn app(jobs_help: &String) -> Command {
     app
 }
 
+const AT: &str = "at";
+const BY_DEFAULT_FEATURES: &str = "by-default-features";
+const BY_MAJOR: &str = "by-major";
+const BY_VERSION: &str = "by-version";
+const CDN: &str = "cdn";
+const CHANGE: &str = "change";
 const DB: &str = "db";
+const DEPENDENCIES_OF: &str = "dependencies-of";
+const DEPTH: &str = "depth";
+const DIFF: &str = "diff";
 const EXCLUDE: &str = "exclude";
+const EXPLAIN: &str = "explain";
+const FORMAT: &str = "format";
+const INTERVAL: &str = "interval";
 const JOBS: &str = "jobs";
+const KIND: &str = "kind";
+const NO_OPEN: &str = "no-open";
+const OPTIONAL: &str = "optional";
+const OUTPUT: &str = "output";
+const PLOT: &str = "plot";
 const RELATIVE: &str = "relative";
+const SINCE: &str = "since";
 const TITLE: &str = "title";
+const TOP: &str = "top";
 const TRANSITIVE: &str = "transitive";
+const TRENDING: &str = "trending";
+const UNTIL: &str = "until";
 const QUERIES: &str = "queries";
 
 pub(crate) fn parse() -> Opt {
+    parse_from(env::args_os())
+}
+
+pub(crate) fn parse_from(args: impl IntoIterator<Item = OsString>) -> Opt {
     // | threads | duration | allocated |  peak   |
     // |---------|----------|-----------|---------|
     // |     1   |  38.6 s  |   55.2 GB | 11.0 GB |
@@ -76,50 +153,200 @@ pub(crate) fn parse() -> Opt {
         default_jobs,
     );
 
-    let mut args: Vec<_> = env::args_os().collect();
+    let mut args: Vec<_> = args.into_iter().collect();
     if let Some(first) = args.get_mut(0) {
         *first = OsString::from("cargo-tally");
     }
     if args.get(1).map(OsString::as_os_str) == Some(OsStr::new("tally")) {
         args.remove(1);
     }
-    let matches = app(&jobs_help).get_matches_from(args);
+    let mut app = app(&jobs_help);
+    let matches = app
+        .try_get_matches_from_mut(args)
+        .unwrap_or_else(|err| err.exit());
 
     let db = PathBuf::from(matches.get_one::<PathBuf>(DB).unwrap());
 
+    let diff = matches
+        .get_one::<(NaiveDate, NaiveDate)>(DIFF)
+        .map(|(since, until)| {
+            (
+                DateTime::new(*since, NaiveTime::MIN),
+                DateTime::new(until.succ_opt().unwrap(), NaiveTime::MIN),
+            )
+        });
+
+    let depth = matches
+        .get_many::<u16>(DEPTH)
+        .unwrap_or_default()
+        .copied()
+        .collect();
+
     let exclude = matches
         .get_many::<Regex>(EXCLUDE)
         .unwrap_or_default()
         .cloned()
         .collect();
 
+    let explain = matches.get_one::<String>(EXPLAIN).map(String::clone);
+
+    let format = matches.get_one::<Format>(FORMAT).copied();
+
+    let interval = matches.get_one::<Interval>(INTERVAL).copied();
+
     let jobs = matches
         .get_one::<usize>(JOBS)
         .copied()
         .unwrap_or(default_jobs);
 
+    let kind = matches
+        .get_many::<DependencyKind>(KIND)
+        .map(|kinds| kinds.copied().collect());
+
+    let optional = matches
+        .get_one::<Optional>(OPTIONAL)
+        .copied()
+        .unwrap_or(Optional::Include);
+
+    let output = matches.get_one::<PathBuf>(OUTPUT).map(PathBuf::clone);
+
+    let plot = matches.get_one::<Plot>(PLOT).copied().unwrap_or(Plot::Html);
+    if plot == Plot::Term && (format.is_some() || output.is_some()) {
+        app.error(
+            ErrorKind::ArgumentConflict,
+            "--plot term draws to the terminal and cannot be used with --format or --output",
+        )
+        .exit();
+    }
+
     let title = matches.get_one::<String>(TITLE).map(String::clone);
 
+    let top = matches.get_one::<usize>(TOP).copied();
+    let trending = matches.get_flag(TRENDING);
+    let change = matches.get_one::<u32>(CHANGE).copied();
+    if change.is_some() && top.is_none() && !trending {
+        app.error(
+            ErrorKind::MissingRequiredArgument,
+            "--change only applies to the rankings printed by --top or --trending",
+        )
+        .exit();
+    }
+
+    // Both ends of the window are inclusive of the whole day given.
+    let since = matches
+        .get_one::<NaiveDate>(SINCE)
+        .map(|date| DateTime::new(*date, NaiveTime::MIN));
+    let at = matches
+        .get_one::<NaiveDate>(AT)
+        .map(|date| DateTime::new(date.succ_opt().unwrap(), NaiveTime::MIN));
+    let until = matches
+        .get_one::<NaiveDate>(UNTIL)
+        .map(|date| DateTime::new(date.succ_opt().unwrap(), NaiveTime::MIN));
+
+    let by_default_features = matches.get_flag(BY_DEFAULT_FEATURES);
+    let by_major = matches.get_flag(BY_MAJOR);
+    let by_version = matches.get_flag(BY_VERSION);
+    let cdn = matches.get_flag(CDN);
+    let no_open = matches.get_flag(NO_OPEN);
     let relative = matches.get_flag(RELATIVE);
     let transitive = matches.get_flag(TRANSITIVE);
 
+    // The crates given to --dependencies-of take the place of the queries,
+    // counting what they depend on rather than what depends on them.
+    let dependencies_of = matches.contains_id(DEPENDENCIES_OF);
     let queries = matches
-        .get_many::<String>(QUERIES)
-        .unwrap()
+        .get_many::<String>(if dependencies_of {
+            DEPENDENCIES_OF
+        } else {
+            QUERIES
+        })
+        .unwrap_or_default()
         .map(String::clone)
         .collect();
 
     Opt {
+        at,
+        by_default_features,
+        by_major,
+        by_version,
+        cdn,
+        change,
         db,
+        dependencies_of,
+        depth,
+        diff,
         exclude,
+        explain,
+        format,
+        interval,
         jobs,
+        kind,
+        no_open,
+        optional,
+        output,
+        plot,
         relative,
+        since,
         title,
+        top,
         transitive,
+        trending,
+        until,
         queries,
     }
 }
 
+fn arg_at() -> Arg {
+    Arg::new(AT)
+        .long(AT)
+        .num_args(1)
+        .value_name("DATE")
+        .value_parser(NaiveDate::from_str)
+        .conflicts_with_all([FORMAT, INTERVAL, OUTPUT, PLOT, SINCE, UNTIL])
+        .help("List the crates counted in each query as of the end of this date")
+}
+
+fn arg_by_default_features() -> Arg {
+    Arg::new(BY_DEFAULT_FEATURES)
+        .long(BY_DEFAULT_FEATURES)
+        .num_args(0)
+        .help("Split each query by whether dependents keep or disable default features")
+}
+
+fn arg_by_major() -> Arg {
+    Arg::new(BY_MAJOR)
+        .long(BY_MAJOR)
+        .num_args(0)
+        .conflicts_with(DEPENDENCIES_OF)
+        .help("Split each query into one series per semver-incompatible release line of the crate")
+}
+
+fn arg_by_version() -> Arg {
+    Arg::new(BY_VERSION)
+        .long(BY_VERSION)
+        .num_args(0)
+        .conflicts_with_all([BY_MAJOR, DEPENDENCIES_OF])
+        .help(
+            "Split each query into one series per release of the crate that dependents resolve to",
+        )
+}
+
+fn arg_cdn() -> Arg {
+    Arg::new(CDN)
+        .long(CDN)
+        .num_args(0)
+        .help("Load D3 from d3js.org instead of embedding it in the graph")
+}
+
+fn arg_change() -> Arg {
+    Arg::new(CHANGE)
+        .long(CHANGE)
+        .num_args(1)
+        .value_name("MONTHS")
+        .value_parser(clap::value_parser!(u32).range(1..))
+        .help("Window in months over which --top and --trending compare each count")
+}
+
 fn arg_db() -> Arg {
     Arg::new(DB)
         .long(DB)
@@ -130,6 +357,38 @@ fn arg_db() -> Arg {
         .help("Path to crates.io's database dump")
 }
 
+fn arg_dependencies_of() -> Arg {
+    Arg::new(DEPENDENCIES_OF)
+        .long(DEPENDENCIES_OF)
+        .action(ArgAction::Append)
+        .value_name("CRATE")
+        .value_parser(validate_root)
+        .conflicts_with_all([BY_DEFAULT_FEATURES, EXPLAIN, QUERIES])
+        .help("Count the dependencies of this crate's most recent release instead")
+}
+
+fn arg_depth() -> Arg {
+    Arg::new(DEPTH)
+        .long(DEPTH)
+        .action(ArgAction::Append)
+        .value_name("N")
+        .value_delimiter(',')
+        .value_parser(clap::value_parser!(u16).range(1..))
+        .requires(TRANSITIVE)
+        .conflicts_with_all([DEPENDENCIES_OF, EXPLAIN])
+        .help("Count only dependents within this many dependencies of each query")
+}
+
+fn arg_diff() -> Arg {
+    Arg::new(DIFF)
+        .long(DIFF)
+        .num_args(1)
+        .value_name("DATE..DATE")
+        .value_parser(parse_date_range)
+        .conflicts_with_all([AT, FORMAT, INTERVAL, OUTPUT, PLOT, SINCE, UNTIL])
+        .help("List the crates that started or stopped being counted between two dates")
+}
+
 fn arg_exclude() -> Arg {
     Arg::new(EXCLUDE)
         .long(EXCLUDE)
@@ -140,6 +399,47 @@ fn arg_exclude() -> Arg {
         .help("Ignore a dependency coming from any crates matching regex")
 }
 
+fn arg_explain() -> Arg {
+    Arg::new(EXPLAIN)
+        .long(EXPLAIN)
+        .num_args(1)
+        .value_name("CRATE")
+        .value_parser(validate_crate_name)
+        .conflicts_with_all([AT, DIFF, FORMAT, INTERVAL, OUTPUT, PLOT, SINCE, UNTIL])
+        .help("Show the shortest chain by which this crate depends on each query")
+}
+
+fn arg_format() -> Arg {
+    Arg::new(FORMAT)
+        .long(FORMAT)
+        .num_args(1)
+        .value_name("FORMAT")
+        .value_parser(
+            PossibleValuesParser::new(Format::NAMES)
+                .map(|format| Format::from_str(&format).unwrap()),
+        )
+        .help("Print every row of the result in a machine-readable format")
+}
+
+fn arg_interval() -> Arg {
+    Arg::new(INTERVAL)
+        .long(INTERVAL)
+        .num_args(1)
+        .value_name("PERIOD")
+        .value_parser(
+            PossibleValuesParser::new(["day", "week", "month", "quarter"]).map(|interval| {
+                match interval.as_str() {
+                    "day" => Interval::Day,
+                    "week" => Interval::Week,
+                    "month" => Interval::Month,
+                    "quarter" => Interval::Quarter,
+                    _ => unreachable!(),
+                }
+            }),
+        )
+        .help("Resample to one value at the end of each day, week, month or quarter")
+}
+
 fn arg_jobs(help: &String) -> Arg {
     Arg::new(JOBS)
         .long(JOBS)
@@ -150,6 +450,61 @@ fn arg_jobs(help: &String) -> Arg {
         .help(help)
 }
 
+fn arg_kind() -> Arg {
+    Arg::new(KIND)
+        .long(KIND)
+        .action(ArgAction::Append)
+        .value_name("KIND")
+        .value_delimiter(',')
+        .value_parser(
+            PossibleValuesParser::new(DependencyKind::NAMES)
+                .map(|kind| DependencyKind::from_str(&kind).unwrap()),
+        )
+        .help("Count only these kinds of dependency: normal, build, dev")
+}
+
+fn arg_no_open() -> Arg {
+    Arg::new(NO_OPEN)
+        .long(NO_OPEN)
+        .num_args(0)
+        .help("Do not open the graph in a browser")
+}
+
+fn arg_optional() -> Arg {
+    Arg::new(OPTIONAL)
+        .long(OPTIONAL)
+        .num_args(1)
+        .value_name("WHEN")
+        .value_parser(
+            PossibleValuesParser::new(Optional::NAMES)
+                .map(|optional| Optional::from_str(&optional).unwrap()),
+        )
+        .help("Whether to count optional dependencies: include, exclude, only")
+}
+
+fn arg_output() -> Arg {
+    Arg::new(OUTPUT)
+        .long(OUTPUT)
+        .short('o')
+        .num_args(1)
+        .value_name("PATH")
+        .value_parser(ValueParser::path_buf())
+        .help(
+            "Write the graph, or the rows printed by --format, to this path or to stdout if \"-\"",
+        )
+}
+
+fn arg_plot() -> Arg {
+    Arg::new(PLOT)
+        .long(PLOT)
+        .num_args(1)
+        .value_name("PLOT")
+        .value_parser(
+            PossibleValuesParser::new(Plot::NAMES).map(|plot| Plot::from_str(&plot).unwrap()),
+        )
+        .help("Draw the graph in a browser (html) or in the terminal (term)")
+}
+
 fn arg_relative() -> Arg {
     Arg::new(RELATIVE)
         .long(RELATIVE)
@@ -157,6 +512,15 @@ fn arg_relative() -> Arg {
         .help("Display as a fraction of total crates, not absolute number")
 }
 
+fn arg_since() -> Arg {
+    Arg::new(SINCE)
+        .long(SINCE)
+        .num_args(1)
+        .value_name("DATE")
+        .value_parser(NaiveDate::from_str)
+        .help("Leave out data before this date (YYYY-MM-DD)")
+}
+
 fn arg_title() -> Arg {
     Arg::new(TITLE)
         .long(TITLE)
@@ -167,6 +531,33 @@ fn arg_title() -> Arg {
         .help("Graph title")
 }
 
+fn arg_top() -> Arg {
+    Arg::new(TOP)
+        .long(TOP)
+        .num_args(1)
+        .value_name("N")
+        .value_parser(usize::from_str)
+        .conflicts_with_all([
+            BY_DEFAULT_FEATURES,
+            BY_MAJOR,
+            BY_VERSION,
+            DEPENDENCIES_OF,
+            DEPTH,
+            DIFF,
+            EXPLAIN,
+            FORMAT,
+            INTERVAL,
+            OUTPUT,
+            PLOT,
+            QUERIES,
+            RELATIVE,
+            SINCE,
+            TRANSITIVE,
+            UNTIL,
+        ])
+        .help("Rank every crate by its number of dependents and print the first N")
+}
+
 fn arg_transitive() -> Arg {
     Arg::new(TRANSITIVE)
         .long(TRANSITIVE)
@@ -174,9 +565,43 @@ fn arg_transitive() -> Arg {
         .help("Count transitive dependencies, not just direct dependencies")
 }
 
+fn arg_trending() -> Arg {
+    Arg::new(TRENDING)
+        .long(TRENDING)
+        .num_args(0)
+        .conflicts_with_all([
+            BY_DEFAULT_FEATURES,
+            BY_MAJOR,
+            BY_VERSION,
+            DEPENDENCIES_OF,
+            DEPTH,
+            DIFF,
+            EXPLAIN,
+            FORMAT,
+            INTERVAL,
+            OUTPUT,
+            PLOT,
+            QUERIES,
+            RELATIVE,
+            SINCE,
+            TRANSITIVE,
+            UNTIL,
+        ])
+        .help("List the crates whose number of dependents grew or declined the most")
+}
+
+fn arg_until() -> Arg {
+    Arg::new(UNTIL)
+        .long(UNTIL)
+        .num_args(1)
+        .value_name("DATE")
+        .value_parser(NaiveDate::from_str)
+        .help("Leave out data after this date (YYYY-MM-DD)")
+}
+
 fn arg_queries() -> Arg {
     Arg::new(QUERIES)
-        .required(true)
+        .required_unless_present_any([DEPENDENCIES_OF, TOP, TRENDING])
         .num_args(0..)
         .value_name("QUERIES")
         .value_parser(validate_query)
@@ -190,38 +615,192 @@ enum Error {
     InvalidUsername,
     #[error("invalid crate name according to crates.io")]
     InvalidCrateName,
+    #[error("invalid feature name")]
+    InvalidFeatureName,
+    #[error("invalid category slug")]
+    InvalidCategory,
+    #[error("invalid keyword")]
+    InvalidKeyword,
+    #[error("invalid glob, expected crate name characters and '*' or '?'")]
+    InvalidGlob,
+    #[error("expected a regex between slashes, like re:/^tokio-/")]
+    InvalidRegex,
+    #[error("a regex containing '+', '&', '!', parentheses or spaces must be written between slashes, like re:/^(a|b)$/")]
+    UnslashedRegex,
+    #[error("expected a dependency kind: normal, build or dev")]
+    InvalidDependencyKind,
+    #[error("expected a crate name or @owner, without version, kinds, features or operators")]
+    InvalidRoot,
+    #[error("expected two dates separated by \"..\", like 2022-01-01..2023-01-01")]
+    InvalidDateRange,
+    #[error(transparent)]
+    Date(#[from] chrono::ParseError),
+    #[error(transparent)]
+    Regex(#[from] regex::Error),
     #[error(transparent)]
     Semver(#[from] semver::Error),
+    #[error(transparent)]
+    Query(#[from] ParseError),
+}
+
+fn parse_date_range(string: &str) -> Result<(NaiveDate, NaiveDate), Error> {
+    let Some((since, until)) = string.split_once("..") else {
+        return Err(Error::InvalidDateRange);
+    };
+    let since = NaiveDate::from_str(since)?;
+    let until = NaiveDate::from_str(until)?;
+    if since > until {
+        return Err(Error::InvalidDateRange);
+    }
+    Ok((since, until))
+}
+
+fn validate_crate_name(string: &str) -> Result<String, Error> {
+    if cratename::valid(string) {
+        Ok(string.to_owned())
+    } else {
+        Err(Error::InvalidCrateName)
+    }
 }
 
 fn validate_query(string: &str) -> Result<String, Error> {
-    for predicate in string.split('+') {
-        let predicate = predicate.trim();
-
-        if let Some(username) = predicate.strip_prefix('@') {
-            if username.split('/').all(user::valid) {
-                continue;
-            } else {
-                return Err(Error::InvalidUsername);
-            }
+    let expr = syntax::parse(string)?;
+    for leaf in expr.leaves() {
+        if let Err(err) = validate_predicate(leaf.text) {
+            return Err(Error::Query(ParseError::at_leaf(string, leaf, err)));
         }
+    }
+    Ok(string.to_owned())
+}
 
-        let (name, req) = if let Some((name, req)) = predicate.split_once(':') {
-            (name, Some(req))
-        } else {
-            (predicate, None)
+// The argument of --dependencies-of names a crate, or an owner standing for each
+// of their crates, and counts from its most recent release. Anything else that
+// a query can say about dependents would be ignored, so it is not accepted.
+fn validate_root(string: &str) -> Result<String, Error> {
+    let valid = match string.strip_prefix('@') {
+        Some(username) => username.split('/').all(user::valid),
+        None => cratename::valid(string),
+    };
+    if valid {
+        Ok(string.to_owned())
+    } else {
+        Err(Error::InvalidRoot)
+    }
+}
+
+fn validate_predicate(predicate: &str) -> Result<(), Error> {
+    if predicate.starts_with("re:/") {
+        let Some((regex, suffix)) = syntax::split_regex(predicate) else {
+            return Err(Error::InvalidRegex);
         };
+        if !validate_kinds(suffix.trim_start())?.is_empty() {
+            return Err(Error::InvalidRegex);
+        }
+        Regex::new(regex)?;
+        return Ok(());
+    }
 
-        if !cratename::valid(name.trim()) {
-            return Err(Error::InvalidCrateName);
+    if let Some(regex) = predicate.strip_prefix("re:") {
+        let regex = validate_kinds(regex).unwrap_or(regex);
+        if regex.contains(syntax::is_operator) {
+            return Err(Error::UnslashedRegex);
         }
+        Regex::new(regex)?;
+        return Ok(());
+    }
+
+    let mut predicate = validate_kinds(predicate)?;
 
-        if let Some(req) = req {
-            VersionReq::from_str(req)?;
+    if let Some(glob) = predicate.strip_prefix("glob:") {
+        return if !glob.is_empty()
+            && glob
+                .chars()
+                .all(|ch| ch.is_ascii_alphanumeric() || matches!(ch, '-' | '_' | '*' | '?'))
+        {
+            Ok(())
+        } else {
+            Err(Error::InvalidGlob)
+        };
+    }
+
+    if let Some(slug) = predicate.strip_prefix("category:") {
+        return if !slug.is_empty()
+            && slug.split("::").all(|part| {
+                !part.is_empty()
+                    && part
+                        .chars()
+                        .all(|ch| ch.is_ascii_alphanumeric() || ch == '-')
+            }) {
+            Ok(())
+        } else {
+            Err(Error::InvalidCategory)
+        };
+    }
+
+    if let Some(keyword) = predicate.strip_prefix("keyword:") {
+        return if !keyword.is_empty()
+            && keyword
+                .chars()
+                .all(|ch| ch.is_alphanumeric() || matches!(ch, '-' | '_'))
+        {
+            Ok(())
+        } else {
+            Err(Error::InvalidKeyword)
+        };
+    }
+
+    if let Some(username) = predicate.strip_prefix('@') {
+        return if username.split('/').all(user::valid) {
+            Ok(())
+        } else {
+            Err(Error::InvalidUsername)
+        };
+    }
+
+    if let Some((rest, feature)) = predicate.split_once('/') {
+        let feature = feature.trim();
+        if feature.is_empty()
+            || !feature
+                .chars()
+                .all(|ch| ch.is_alphanumeric() || matches!(ch, '-' | '_' | '.'))
+        {
+            return Err(Error::InvalidFeatureName);
         }
+        predicate = rest;
     }
 
-    Ok(string.to_owned())
+    let (name, req) = if let Some((name, req)) = predicate.split_once(':') {
+        (name, Some(req))
+    } else {
+        (predicate, None)
+    };
+
+    if !cratename::valid(name.trim()) {
+        return Err(Error::InvalidCrateName);
+    }
+
+    if let Some(req) = req {
+        VersionReq::from_str(req)?;
+    }
+
+    Ok(())
+}
+
+// Checks a trailing list of dependency kinds like "[build,normal]", if any,
+// and returns what comes before it.
+fn validate_kinds(predicate: &str) -> Result<&str, Error> {
+    let Some(rest) = predicate.strip_suffix(']') else {
+        return Ok(predicate);
+    };
+    let Some((rest, kinds)) = rest.rsplit_once('[') else {
+        return Err(Error::InvalidDependencyKind);
+    };
+    for kind in kinds.split(',') {
+        if DependencyKind::from_str(kind.trim()).is_err() {
+            return Err(Error::InvalidDependencyKind);
+        }
+    }
+    Ok(rest.trim_end())
 }
 
 #[test]
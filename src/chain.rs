@@ -0,0 +1,184 @@
+use crate::cratemap::CrateMap;
+use crate::dependents::Releases;
+use cargo_tally::dependency::DependencyKind;
+use cargo_tally::explain::{self, Edge, EdgeKind, Explanation};
+use cargo_tally::feature::{FeatureId, FeatureNames, VersionFeature};
+use cargo_tally::id::CrateId;
+use cargo_tally::{Config, DbDump, Op, Query};
+use std::io::{self, Write};
+
+#[allow(clippy::too_many_arguments)]
+pub(crate) fn print(
+    out: &mut dyn Write,
+    db_dump: &DbDump,
+    config: Config,
+    queries: &[Query],
+    dependent: CrateId,
+    crates: &CrateMap,
+    labels: &[String],
+    term_labels: &[Vec<String>],
+) -> io::Result<()> {
+    let releases = Releases::index(&db_dump.releases);
+    let print = PrintTerm {
+        name: crates.name(dependent).unwrap(),
+        how: if config.transitive {
+            "transitively"
+        } else {
+            "directly"
+        },
+        display: DisplayEdge {
+            releases: &releases,
+            crates,
+            features: &db_dump.features,
+        },
+    };
+
+    for (i, query) in queries.iter().enumerate() {
+        if i > 0 {
+            writeln!(out)?;
+        }
+        let explanations: Vec<Option<Explanation>> = query
+            .terms
+            .iter()
+            .map(|term| explain::explain(db_dump, config, term, dependent))
+            .collect();
+        let ops: Vec<Op> = query.expr.iter().collect();
+        if let [Op::Term(_)] = ops.as_slice() {
+            print.term(out, "", &labels[i], explanations[0].as_ref())?;
+            continue;
+        }
+        // A query with operators is explained one term at a time.
+        let counted = cargo_tally::eval(query.expr, |term_id| {
+            explanations[term_id.0 as usize].is_some()
+        });
+        let is = if counted { "is" } else { "is not" };
+        writeln!(out, "{} {} counted by {}:", print.name, is, labels[i])?;
+        for (label, explanation) in term_labels[i].iter().zip(&explanations) {
+            print.term(out, "    ", label, explanation.as_ref())?;
+        }
+    }
+    Ok(())
+}
+
+struct PrintTerm<'a> {
+    name: &'a str,
+    how: &'static str,
+    display: DisplayEdge<'a>,
+}
+
+impl<'a> PrintTerm<'a> {
+    fn term(
+        &self,
+        out: &mut dyn Write,
+        indent: &str,
+        label: &str,
+        explanation: Option<&Explanation>,
+    ) -> io::Result<()> {
+        let name = self.name;
+        let how = self.how;
+        match explanation {
+            None => writeln!(
+                out,
+                "{}{} does not depend {} on {}",
+                indent, name, how, label
+            )?,
+            Some(Explanation::Matches(version_id)) => {
+                let (_crate_id, version) = self.display.releases.get(*version_id);
+                writeln!(
+                    out,
+                    "{}{} {} is itself matched by {}",
+                    indent, name, version, label,
+                )?;
+            }
+            Some(Explanation::Path(path)) => {
+                writeln!(out, "{}{} depends {} on {}:", indent, name, how, label)?;
+                for edge in path {
+                    if let EdgeKind::FeatureIntracrate = edge.kind {
+                        if edge.to.feature_id == FeatureId::CRATE {
+                            // Every feature of a crate implies the crate itself.
+                            continue;
+                        }
+                    }
+                    write!(out, "{}    ", indent)?;
+                    self.display.write(out, edge)?;
+                    writeln!(out)?;
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+struct DisplayEdge<'a> {
+    releases: &'a Releases,
+    crates: &'a CrateMap,
+    features: &'a FeatureNames,
+}
+
+impl<'a> DisplayEdge<'a> {
+    fn write(&self, out: &mut dyn Write, edge: &Edge) -> io::Result<()> {
+        match edge.kind {
+            EdgeKind::Dependency(kind) => {
+                self.write_release(out, edge.from)?;
+                write!(out, " depends on ")?;
+                self.write_release(out, edge.to)?;
+                match edge.to.feature_id {
+                    FeatureId::CRATE => {}
+                    FeatureId::DEFAULT => write!(out, " with default features")?,
+                    feature_id => {
+                        write!(out, " with feature {:?}", self.features.name(feature_id))?;
+                    }
+                }
+                match kind {
+                    DependencyKind::Normal => {}
+                    DependencyKind::Build => write!(out, " [build-dependencies]")?,
+                    DependencyKind::Dev => write!(out, " [dev-dependencies]")?,
+                }
+                if edge.from.feature_id != FeatureId::CRATE {
+                    let feature = self.features.name(edge.from.feature_id);
+                    write!(out, " (optional, enabled by feature {:?})", feature)?;
+                }
+            }
+            EdgeKind::FeatureIntracrate => {
+                self.write_release(out, edge.from)?;
+                write!(
+                    out,
+                    " feature {:?} enables feature {:?}",
+                    self.features.name(edge.from.feature_id),
+                    self.features.name(edge.to.feature_id),
+                )?;
+            }
+            EdgeKind::FeatureDependency => {
+                self.write_release(out, edge.from)?;
+                write!(
+                    out,
+                    " feature {:?} enables feature {:?} of ",
+                    self.features.name(edge.from.feature_id),
+                    self.features.name(edge.to.feature_id),
+                )?;
+                self.write_release(out, edge.to)?;
+            }
+            EdgeKind::WeakFeatureDependency(version_id) => {
+                self.write_release(out, edge.from)?;
+                write!(out, " turns on features of ")?;
+                let via = VersionFeature {
+                    version_id,
+                    feature_id: FeatureId::CRATE,
+                };
+                self.write_release(out, via)?;
+                write!(
+                    out,
+                    " which together enable feature {:?} of ",
+                    self.features.name(edge.to.feature_id),
+                )?;
+                self.write_release(out, edge.to)?;
+            }
+        }
+        Ok(())
+    }
+
+    fn write_release(&self, out: &mut dyn Write, node: VersionFeature) -> io::Result<()> {
+        let (crate_id, version) = self.releases.get(node.version_id);
+        write!(out, "{} {}", self.crates.name(crate_id).unwrap(), version)
+    }
+}
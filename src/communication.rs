@@ -40,9 +40,12 @@ macro_rules! do_not_abomonate {
 }
 
 do_not_abomonate!(crate::Dependency);
+do_not_abomonate!(crate::Op);
 do_not_abomonate!(crate::Query);
 do_not_abomonate!(crate::Release);
 do_not_abomonate!(crate::arena::Slice<T> where T: 'static);
+do_not_abomonate!(crate::dependency::DependencyKind);
+do_not_abomonate!(crate::dependency::DependencyKinds);
 do_not_abomonate!(crate::feature::CrateFeature);
 do_not_abomonate!(crate::feature::DefaultFeatures);
 do_not_abomonate!(crate::feature::FeatureId);
@@ -50,6 +53,7 @@ do_not_abomonate!(crate::feature::VersionFeature);
 do_not_abomonate!(crate::id::CrateId);
 do_not_abomonate!(crate::id::DependencyId);
 do_not_abomonate!(crate::id::QueryId);
+do_not_abomonate!(crate::id::TermId);
 do_not_abomonate!(crate::id::VersionId);
 do_not_abomonate!(crate::max::Max<T>);
 do_not_abomonate!(crate::present::Present);
@@ -11,6 +11,10 @@ pub struct CrateMap {
     ids: Map<CrateName, CrateId>,
     pub(crate) users: Map<User, OwnerId>,
     pub(crate) owners: Map<OwnerId, Vec<CrateId>>,
+    // Crates in each category by its slug, like "cryptography" or
+    // "cryptography::cryptocurrencies".
+    pub(crate) categories: Map<String, Vec<CrateId>>,
+    pub(crate) keywords: Map<String, Vec<CrateId>>,
 }
 
 impl CrateMap {
@@ -32,4 +36,10 @@ impl CrateMap {
     pub fn id(&self, name: &str) -> Option<CrateId> {
         self.ids.get(CrateNameQuery::ref_cast(name)).copied()
     }
+
+    pub fn iter(&self) -> impl Iterator<Item = (CrateId, &str)> {
+        self.names
+            .iter()
+            .map(|(crate_id, name)| (*crate_id, name.as_str()))
+    }
 }
@@ -0,0 +1,661 @@
+// The subset of the D3 v7 API used by index.html, so that a graph renders
+// without network access. Behavior follows the corresponding D3 modules
+// (d3-array, d3-axis, d3-format, d3-interpolate, d3-scale,
+// d3-scale-chromatic, d3-selection, d3-shape, d3-time, d3-time-format). Pass
+// --cdn to load the complete D3 from d3js.org instead.
+var d3 = (function() {
+  var svgNamespace = "http://www.w3.org/2000/svg";
+
+  // d3-array
+
+  function min(values, accessor) {
+    var result;
+    values.forEach(function(value, i) {
+      value = accessor(value, i, values);
+      if (value != null && (result === undefined || value < result)) {
+        result = value;
+      }
+    });
+    return result;
+  }
+
+  function max(values, accessor) {
+    var result;
+    values.forEach(function(value, i) {
+      value = accessor(value, i, values);
+      if (value != null && (result === undefined || value > result)) {
+        result = value;
+      }
+    });
+    return result;
+  }
+
+  function bisector(accessor) {
+    return {
+      right: function(array, x) {
+        var lo = 0;
+        var hi = array.length;
+        while (lo < hi) {
+          var mid = (lo + hi) >>> 1;
+          if (accessor(array[mid]) <= x) {
+            lo = mid + 1;
+          } else {
+            hi = mid;
+          }
+        }
+        return lo;
+      },
+    };
+  }
+
+  function tickSpec(start, stop, count) {
+    var step = (stop - start) / Math.max(0, count);
+    var power = Math.floor(Math.log10(step));
+    var error = step / Math.pow(10, power);
+    var factor = error >= Math.sqrt(50) ? 10 : error >= Math.sqrt(10) ? 5 : error >= Math.sqrt(2) ? 2 : 1;
+    var i1, i2, inc;
+    if (power < 0) {
+      inc = Math.pow(10, -power) / factor;
+      i1 = Math.round(start * inc);
+      i2 = Math.round(stop * inc);
+      if (i1 / inc < start) ++i1;
+      if (i2 / inc > stop) --i2;
+      inc = -inc;
+    } else {
+      inc = Math.pow(10, power) * factor;
+      i1 = Math.round(start / inc);
+      i2 = Math.round(stop / inc);
+      if (i1 * inc < start) ++i1;
+      if (i2 * inc > stop) --i2;
+    }
+    if (i2 < i1 && 0.5 <= count && count < 2) {
+      return tickSpec(start, stop, count * 2);
+    }
+    return [i1, i2, inc];
+  }
+
+  function ticks(start, stop, count) {
+    if (!(count > 0) || !(stop > start)) {
+      return start === stop && count > 0 ? [start] : [];
+    }
+    var spec = tickSpec(start, stop, count);
+    var result = [];
+    for (var i = spec[0]; i <= spec[1]; i++) {
+      result.push(spec[2] < 0 ? i / -spec[2] : i * spec[2]);
+    }
+    return result;
+  }
+
+  function tickStep(start, stop, count) {
+    var inc = tickSpec(start, stop, count)[2];
+    return inc < 0 ? -1 / inc : inc;
+  }
+
+  // d3-format
+
+  function exponent(x) {
+    return x ? +x.toExponential().split("e")[1] : 0;
+  }
+
+  function precisionFixed(step) {
+    return Math.max(0, -exponent(Math.abs(step)));
+  }
+
+  function group(digits) {
+    return digits.replace(/\B(?=(\d{3})+(?!\d))/g, ",");
+  }
+
+  function format(specifier) {
+    var match = /^(,)?(?:\.(\d+))?([f%])?$/.exec(specifier);
+    if (!match) {
+      throw new Error("unsupported format: " + specifier);
+    }
+    var comma = !!match[1];
+    var precision = match[2] === undefined ? undefined : +match[2];
+    var type = match[3];
+    return function(value) {
+      var string;
+      if (type === "%") {
+        string = (value * 100).toFixed(precision === undefined ? 6 : precision);
+      } else if (type === "f") {
+        string = value.toFixed(precision === undefined ? 6 : precision);
+      } else {
+        string = String(+value.toPrecision(precision === undefined ? 12 : precision));
+      }
+      var negative = string.charAt(0) === "-";
+      if (negative) string = string.slice(1);
+      if (comma) {
+        var point = string.indexOf(".");
+        string = point < 0 ? group(string) : group(string.slice(0, point)) + string.slice(point);
+      }
+      if (negative && +string.replace(/[^0-9]/g, "")) string = "−" + string;
+      return type === "%" ? string + "%" : string;
+    };
+  }
+
+  // d3-interpolate
+
+  function interpolateNumber(a, b) {
+    return function(t) {
+      return a * (1 - t) + b * t;
+    };
+  }
+
+  // d3-time
+
+  function interval(floor, offset, field, duration) {
+    var self = {
+      floor: function(date) {
+        date = new Date(+date);
+        floor(date);
+        return date;
+      },
+      ceil: function(date) {
+        date = new Date(date - 1);
+        floor(date);
+        offset(date, 1);
+        floor(date);
+        return date;
+      },
+      range: function(start, stop, test) {
+        var result = [];
+        var date = self.ceil(start);
+        while (date < stop) {
+          if (!test || test(date)) result.push(new Date(+date));
+          offset(date, 1);
+          floor(date);
+        }
+        return result;
+      },
+      every: function(step) {
+        step = Math.floor(step);
+        if (!(step > 1)) return self;
+        return {
+          range: function(start, stop) {
+            return self.range(start, stop, function(date) {
+              return field(date) % step === 0;
+            });
+          },
+        };
+      },
+      duration: duration,
+    };
+    return self;
+  }
+
+  var durationSecond = 1e3;
+  var durationMinute = durationSecond * 60;
+  var durationHour = durationMinute * 60;
+  var durationDay = durationHour * 24;
+  var durationWeek = durationDay * 7;
+  var durationMonth = durationDay * 30;
+  var durationYear = durationDay * 365;
+
+  var timeSecond = interval(function(date) {
+    date.setMilliseconds(0);
+  }, function(date, step) {
+    date.setTime(+date + step * durationSecond);
+  }, function(date) {
+    return date.getSeconds();
+  }, durationSecond);
+
+  var timeMinute = interval(function(date) {
+    date.setSeconds(0, 0);
+  }, function(date, step) {
+    date.setTime(+date + step * durationMinute);
+  }, function(date) {
+    return date.getMinutes();
+  }, durationMinute);
+
+  var timeHour = interval(function(date) {
+    date.setMinutes(0, 0, 0);
+  }, function(date, step) {
+    date.setTime(+date + step * durationHour);
+  }, function(date) {
+    return date.getHours();
+  }, durationHour);
+
+  var timeDay = interval(function(date) {
+    date.setHours(0, 0, 0, 0);
+  }, function(date, step) {
+    date.setDate(date.getDate() + step);
+  }, function(date) {
+    return date.getDate() - 1;
+  }, durationDay);
+
+  var timeWeek = interval(function(date) {
+    date.setDate(date.getDate() - date.getDay());
+    date.setHours(0, 0, 0, 0);
+  }, function(date, step) {
+    date.setDate(date.getDate() + step * 7);
+  }, null, durationWeek);
+
+  var timeMonth = interval(function(date) {
+    date.setDate(1);
+    date.setHours(0, 0, 0, 0);
+  }, function(date, step) {
+    date.setMonth(date.getMonth() + step);
+  }, function(date) {
+    return date.getMonth();
+  }, durationMonth);
+
+  var timeYear = interval(function(date) {
+    date.setMonth(0, 1);
+    date.setHours(0, 0, 0, 0);
+  }, function(date, step) {
+    date.setFullYear(date.getFullYear() + step);
+  }, function(date) {
+    return date.getFullYear();
+  }, durationYear);
+
+  var tickIntervals = [
+    [timeSecond, 1], [timeSecond, 5], [timeSecond, 15], [timeSecond, 30],
+    [timeMinute, 1], [timeMinute, 5], [timeMinute, 15], [timeMinute, 30],
+    [timeHour, 1], [timeHour, 3], [timeHour, 6], [timeHour, 12],
+    [timeDay, 1], [timeDay, 2], [timeWeek, 1], [timeMonth, 1], [timeMonth, 3],
+    [timeYear, 1],
+  ];
+
+  function tickIntervalDuration(i) {
+    return tickIntervals[i][0].duration * tickIntervals[i][1];
+  }
+
+  function timeTicks(start, stop, count) {
+    var target = Math.abs(stop - start) / count;
+    var i = 0;
+    while (i < tickIntervals.length && tickIntervalDuration(i) <= target) {
+      i++;
+    }
+    if (i === tickIntervals.length) {
+      return timeYear.every(tickStep(start / durationYear, stop / durationYear, count)).range(start, +stop + 1);
+    } else if (i === 0) {
+      return ticks(+start, +stop, count).map(function(t) {
+        return new Date(t);
+      });
+    }
+    if (target / tickIntervalDuration(i - 1) < tickIntervalDuration(i) / target) {
+      i--;
+    }
+    return tickIntervals[i][0].every(tickIntervals[i][1]).range(start, +stop + 1);
+  }
+
+  // d3-time-format
+
+  var shortMonths = ["Jan", "Feb", "Mar", "Apr", "May", "Jun", "Jul", "Aug", "Sep", "Oct", "Nov", "Dec"];
+  var months = ["January", "February", "March", "April", "May", "June", "July", "August", "September", "October", "November", "December"];
+  var shortWeekdays = ["Sun", "Mon", "Tue", "Wed", "Thu", "Fri", "Sat"];
+
+  function pad(value, width, fill) {
+    var string = String(value);
+    while (string.length < width) string = fill + string;
+    return string;
+  }
+
+  function timeFormat(specifier) {
+    return function(date) {
+      return specifier.replace(/%(-?)([a-zA-Z%])/g, function(whole, nopad, directive) {
+        var fill = nopad ? "" : "0";
+        switch (directive) {
+          case "a": return shortWeekdays[date.getDay()];
+          case "b": return shortMonths[date.getMonth()];
+          case "B": return months[date.getMonth()];
+          case "d": return pad(date.getDate(), 2, fill);
+          case "H": return pad(date.getHours(), 2, fill);
+          case "I": return pad(date.getHours() % 12 || 12, 2, fill);
+          case "L": return pad(date.getMilliseconds(), 3, fill);
+          case "M": return pad(date.getMinutes(), 2, fill);
+          case "p": return date.getHours() >= 12 ? "PM" : "AM";
+          case "S": return pad(date.getSeconds(), 2, fill);
+          case "Y": return pad(date.getFullYear(), 4, fill);
+          case "%": return "%";
+          default: return whole;
+        }
+      });
+    };
+  }
+
+  var formatMillisecond = timeFormat(".%L");
+  var formatSecond = timeFormat(":%S");
+  var formatMinute = timeFormat("%I:%M");
+  var formatHour = timeFormat("%I %p");
+  var formatDay = timeFormat("%a %d");
+  var formatWeek = timeFormat("%b %d");
+  var formatMonth = timeFormat("%B");
+  var formatYear = timeFormat("%Y");
+
+  function multiFormat(date) {
+    return (timeSecond.floor(date) < date ? formatMillisecond
+      : timeMinute.floor(date) < date ? formatSecond
+      : timeHour.floor(date) < date ? formatMinute
+      : timeDay.floor(date) < date ? formatHour
+      : timeMonth.floor(date) < date ? (timeWeek.floor(date) < date ? formatDay : formatWeek)
+      : timeYear.floor(date) < date ? formatMonth
+      : formatYear)(date);
+  }
+
+  // d3-scale
+
+  function continuous(toNumber, fromNumber) {
+    var domain = [0, 1];
+    var range = [0, 1];
+    function scale(x) {
+      var d0 = toNumber(domain[0]);
+      var d1 = toNumber(domain[1]);
+      var t = d1 - d0 ? (toNumber(x) - d0) / (d1 - d0) : 0.5;
+      return range[0] + t * (range[1] - range[0]);
+    }
+    scale.invert = function(y) {
+      var d0 = toNumber(domain[0]);
+      var d1 = toNumber(domain[1]);
+      var t = range[1] - range[0] ? (y - range[0]) / (range[1] - range[0]) : 0.5;
+      return fromNumber(d0 + t * (d1 - d0));
+    };
+    scale.domain = function(values) {
+      if (!arguments.length) return domain.slice();
+      domain = values.map(fromNumber);
+      return scale;
+    };
+    scale.range = function(values) {
+      if (!arguments.length) return range.slice();
+      range = values.slice();
+      return scale;
+    };
+    return scale;
+  }
+
+  function scaleLinear() {
+    var scale = continuous(Number, Number);
+    scale.ticks = function(count) {
+      var domain = scale.domain();
+      return ticks(domain[0], domain[domain.length - 1], count == null ? 10 : count);
+    };
+    scale.tickFormat = function(count) {
+      var domain = scale.domain();
+      var step = tickStep(domain[0], domain[domain.length - 1], count == null ? 10 : count);
+      return format(",." + precisionFixed(step) + "f");
+    };
+    return scale;
+  }
+
+  function scaleTime() {
+    var scale = continuous(Number, function(x) {
+      return new Date(+x);
+    });
+    scale.ticks = function(count) {
+      var domain = scale.domain();
+      return timeTicks(domain[0], domain[domain.length - 1], count == null ? 10 : count);
+    };
+    scale.tickFormat = function() {
+      return multiFormat;
+    };
+    return scale;
+  }
+
+  function scaleOrdinal(range) {
+    var index = new Map();
+    var domain = [];
+    function scale(d) {
+      if (!index.has(d)) {
+        index.set(d, domain.push(d) - 1);
+      }
+      return range[index.get(d) % range.length];
+    }
+    scale.domain = function(values) {
+      index = new Map();
+      domain = [];
+      values.forEach(scale);
+      return scale;
+    };
+    return scale;
+  }
+
+  // d3-scale-chromatic
+
+  var schemeCategory10 = "1f77b4ff7f0e2ca02cd627289467bd8c564be377c27f7f7fbcbd2217becf"
+    .match(/.{6}/g)
+    .map(function(hex) {
+      return "#" + hex;
+    });
+
+  // d3-selection
+
+  function Selection(nodes, parent) {
+    this.nodes = nodes;
+    this.parent = parent;
+  }
+
+  function evaluate(value, node, i, nodes) {
+    return typeof value === "function" ? value.call(node, node.__data__, i, nodes) : value;
+  }
+
+  function create(name, parent) {
+    return name === "svg" || parent.namespaceURI === svgNamespace
+      ? document.createElementNS(svgNamespace, name)
+      : document.createElement(name);
+  }
+
+  Selection.prototype.append = function(name) {
+    return new Selection(this.nodes.map(function(node) {
+      var child = node.appendChild(create(name, node));
+      if ("__data__" in node) child.__data__ = node.__data__;
+      return child;
+    }), this.parent);
+  };
+
+  Selection.prototype.select = function(selector) {
+    var nodes = [];
+    this.nodes.forEach(function(node) {
+      var child = node.querySelector(selector);
+      if (child) {
+        if ("__data__" in node) child.__data__ = node.__data__;
+        nodes.push(child);
+      }
+    });
+    return new Selection(nodes, this.parent);
+  };
+
+  Selection.prototype.selectAll = function() {
+    return new Selection([], this.nodes[0]);
+  };
+
+  Selection.prototype.data = function(data) {
+    var parent = this.parent;
+    return {
+      enter: function() {
+        return {
+          append: function(name) {
+            return new Selection(data.map(function(datum) {
+              var child = parent.appendChild(create(name, parent));
+              child.__data__ = datum;
+              return child;
+            }), parent);
+          },
+        };
+      },
+    };
+  };
+
+  Selection.prototype.attr = function(name, value) {
+    this.nodes.forEach(function(node, i, nodes) {
+      var v = evaluate(value, node, i, nodes);
+      if (v == null) {
+        node.removeAttribute(name);
+      } else {
+        node.setAttribute(name, v);
+      }
+    });
+    return this;
+  };
+
+  Selection.prototype.style = function(name, value) {
+    this.nodes.forEach(function(node, i, nodes) {
+      var v = evaluate(value, node, i, nodes);
+      if (v == null) {
+        node.style.removeProperty(name);
+      } else {
+        node.style.setProperty(name, v);
+      }
+    });
+    return this;
+  };
+
+  Selection.prototype.text = function(value) {
+    this.nodes.forEach(function(node, i, nodes) {
+      var v = evaluate(value, node, i, nodes);
+      node.textContent = v == null ? "" : v;
+    });
+    return this;
+  };
+
+  Selection.prototype.on = function(type, listener) {
+    this.nodes.forEach(function(node) {
+      node.addEventListener(type, function(event) {
+        listener.call(this, event, this.__data__);
+      });
+    });
+    return this;
+  };
+
+  Selection.prototype.call = function(callback) {
+    var args = Array.prototype.slice.call(arguments, 1);
+    callback.apply(null, [this].concat(args));
+    return this;
+  };
+
+  function select(selector) {
+    var node = typeof selector === "string" ? document.querySelector(selector) : selector;
+    return new Selection(node ? [node] : [], document.documentElement);
+  }
+
+  function pointer(event, target) {
+    if (target === undefined) target = event.currentTarget;
+    var svg = target.ownerSVGElement || target;
+    if (svg.createSVGPoint) {
+      var point = svg.createSVGPoint();
+      point.x = event.clientX;
+      point.y = event.clientY;
+      point = point.matrixTransform(target.getScreenCTM().inverse());
+      return [point.x, point.y];
+    }
+    var rect = target.getBoundingClientRect();
+    return [event.clientX - rect.left - target.clientLeft, event.clientY - rect.top - target.clientTop];
+  }
+
+  // d3-axis
+
+  function axis(orient, scale) {
+    var tickFormat = null;
+    var tickSizeInner = 6;
+    var tickSizeOuter = 6;
+    var tickPadding = 3;
+    var offset = typeof window !== "undefined" && window.devicePixelRatio > 1 ? 0 : 0.5;
+    var k = orient === "top" || orient === "left" ? -1 : 1;
+    var horizontal = orient === "top" || orient === "bottom";
+
+    function draw(selection) {
+      var values = scale.ticks ? scale.ticks() : scale.domain();
+      var formatTick = tickFormat == null ? (scale.tickFormat ? scale.tickFormat() : String) : tickFormat;
+      var spacing = Math.max(tickSizeInner, 0) + tickPadding;
+      var range = scale.range();
+      var range0 = +range[0] + offset;
+      var range1 = +range[range.length - 1] + offset;
+
+      selection.nodes.forEach(function(context) {
+        var g = new Selection([context], context.parentNode)
+          .attr("fill", "none")
+          .attr("font-size", 10)
+          .attr("font-family", "sans-serif")
+          .attr("text-anchor", orient === "right" ? "start" : orient === "left" ? "end" : "middle");
+
+        g.append("path")
+          .attr("class", "domain")
+          .attr("stroke", "currentColor")
+          .attr("d", horizontal
+            ? "M" + range0 + "," + k * tickSizeOuter + "V" + offset + "H" + range1 + "V" + k * tickSizeOuter
+            : "M" + k * tickSizeOuter + "," + range0 + "H" + offset + "V" + range1 + "H" + k * tickSizeOuter);
+
+        values.forEach(function(value) {
+          var position = scale(value) + offset;
+          var tick = g.append("g")
+            .attr("class", "tick")
+            .attr("opacity", 1)
+            .attr("transform", horizontal ? "translate(" + position + ",0)" : "translate(0," + position + ")");
+          tick.append("line")
+            .attr("stroke", "currentColor")
+            .attr(horizontal ? "y2" : "x2", k * tickSizeInner);
+          tick.append("text")
+            .attr("fill", "currentColor")
+            .attr(horizontal ? "y" : "x", k * spacing)
+            .attr("dy", orient === "top" ? "0em" : orient === "bottom" ? "0.71em" : "0.32em")
+            .text(formatTick(value));
+        });
+      });
+    }
+
+    draw.tickFormat = function(format) {
+      if (!arguments.length) return tickFormat;
+      tickFormat = format;
+      return draw;
+    };
+
+    draw.tickSize = function(size) {
+      if (!arguments.length) return tickSizeInner;
+      tickSizeInner = tickSizeOuter = +size;
+      return draw;
+    };
+
+    return draw;
+  }
+
+  function axisBottom(scale) {
+    return axis("bottom", scale);
+  }
+
+  function axisLeft(scale) {
+    return axis("left", scale);
+  }
+
+  // d3-shape
+
+  function line() {
+    var x = function(d) {
+      return d[0];
+    };
+    var y = function(d) {
+      return d[1];
+    };
+    function path(data) {
+      return data.map(function(d, i) {
+        return (i ? "L" : "M") + x(d, i, data) + "," + y(d, i, data);
+      }).join("");
+    }
+    path.x = function(accessor) {
+      x = accessor;
+      return path;
+    };
+    path.y = function(accessor) {
+      y = accessor;
+      return path;
+    };
+    return path;
+  }
+
+  return {
+    axisBottom: axisBottom,
+    axisLeft: axisLeft,
+    bisector: bisector,
+    format: format,
+    interpolateNumber: interpolateNumber,
+    line: line,
+    max: max,
+    min: min,
+    pointer: pointer,
+    precisionFixed: precisionFixed,
+    scaleLinear: scaleLinear,
+    scaleOrdinal: scaleOrdinal,
+    scaleTime: scaleTime,
+    schemeCategory10: schemeCategory10,
+    select: select,
+    timeFormat: timeFormat,
+  };
+})();
@@ -1,10 +1,134 @@
-#[derive(Copy, Clone, Debug)]
+use std::fmt::{self, Display};
+use std::str::FromStr;
+
+#[derive(Copy, Clone, Ord, PartialOrd, Eq, PartialEq, Hash, Debug)]
 pub enum DependencyKind {
     Normal,
     Build,
     Dev,
 }
 
+#[derive(Copy, Clone, Ord, PartialOrd, Eq, PartialEq, Hash, Debug)]
+pub struct DependencyKinds {
+    pub normal: bool,
+    pub build: bool,
+    pub dev: bool,
+}
+
+impl DependencyKinds {
+    pub const ALL: Self = DependencyKinds {
+        normal: true,
+        build: true,
+        dev: true,
+    };
+
+    // Everything that is built as part of building the dependent.
+    pub const BUILD: Self = DependencyKinds {
+        normal: true,
+        build: true,
+        dev: false,
+    };
+
+    pub fn contains(self, kind: DependencyKind) -> bool {
+        match kind {
+            DependencyKind::Normal => self.normal,
+            DependencyKind::Build => self.build,
+            DependencyKind::Dev => self.dev,
+        }
+    }
+}
+
+impl Display for DependencyKinds {
+    fn fmt(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        let mut kinds = [
+            DependencyKind::Normal,
+            DependencyKind::Build,
+            DependencyKind::Dev,
+        ]
+        .into_iter()
+        .filter(|kind| self.contains(*kind));
+        if let Some(first) = kinds.next() {
+            Display::fmt(&first, formatter)?;
+            for kind in kinds {
+                write!(formatter, ",{}", kind)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+impl FromIterator<DependencyKind> for DependencyKinds {
+    fn from_iter<I: IntoIterator<Item = DependencyKind>>(iter: I) -> Self {
+        let mut kinds = DependencyKinds {
+            normal: false,
+            build: false,
+            dev: false,
+        };
+        for kind in iter {
+            match kind {
+                DependencyKind::Normal => kinds.normal = true,
+                DependencyKind::Build => kinds.build = true,
+                DependencyKind::Dev => kinds.dev = true,
+            }
+        }
+        kinds
+    }
+}
+
+impl DependencyKind {
+    pub const NAMES: [&'static str; 3] = ["normal", "build", "dev"];
+}
+
+impl FromStr for DependencyKind {
+    type Err = ();
+
+    fn from_str(string: &str) -> Result<Self, Self::Err> {
+        match string {
+            "normal" => Ok(DependencyKind::Normal),
+            "build" => Ok(DependencyKind::Build),
+            "dev" => Ok(DependencyKind::Dev),
+            _ => Err(()),
+        }
+    }
+}
+
+impl Display for DependencyKind {
+    fn fmt(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        formatter.write_str(match self {
+            DependencyKind::Normal => "normal",
+            DependencyKind::Build => "build",
+            DependencyKind::Dev => "dev",
+        })
+    }
+}
+
+// Whether to count optional dependencies, which only take effect when the
+// dependent's feature named after them is enabled.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum Optional {
+    Include,
+    Exclude,
+    // Only what is counted with optional dependencies but not without.
+    Only,
+}
+
+impl Optional {
+    pub const NAMES: [&'static str; 3] = ["include", "exclude", "only"];
+}
+
+impl FromStr for Optional {
+    type Err = ();
+
+    fn from_str(string: &str) -> Result<Self, Self::Err> {
+        match string {
+            "include" => Ok(Optional::Include),
+            "exclude" => Ok(Optional::Exclude),
+            "only" => Ok(Optional::Only),
+            _ => Err(()),
+        }
+    }
+}
+
 impl From<db_dump::dependencies::DependencyKind> for DependencyKind {
     fn from(dependency_kind: db_dump::dependencies::DependencyKind) -> Self {
         match dependency_kind {
@@ -0,0 +1,187 @@
+use crate::cratemap::CrateMap;
+use cargo_tally::id::{CrateId, QueryId, VersionId};
+use cargo_tally::timestamp::DateTime;
+use cargo_tally::version::Version;
+use cargo_tally::Release;
+use std::collections::btree_map::Entry;
+use std::collections::BTreeMap as Map;
+use std::io::{self, Write};
+
+pub(crate) type Changes = [((VersionId, QueryId), DateTime, isize)];
+
+pub(crate) struct Releases {
+    versions: Map<VersionId, (CrateId, Version)>,
+    by_time: Map<DateTime, VersionId>,
+}
+
+impl Releases {
+    pub(crate) fn index(releases: &[Release]) -> Self {
+        let mut versions = Map::new();
+        let mut by_time = Map::new();
+        for rel in releases {
+            versions.insert(rel.id, (rel.crate_id, rel.num.clone()));
+            by_time.entry(rel.created_at).or_insert(rel.id);
+        }
+        Releases { versions, by_time }
+    }
+
+    pub(crate) fn get(&self, version_id: VersionId) -> (CrateId, &Version) {
+        let (crate_id, version) = &self.versions[&version_id];
+        (*crate_id, version)
+    }
+}
+
+// For each query, the name and counted version of every crate contributing to
+// its tally, in alphabetical order.
+pub(crate) fn print_at(
+    out: &mut dyn Write,
+    changes: &Changes,
+    releases: &Releases,
+    crates: &CrateMap,
+    labels: &[String],
+) -> io::Result<()> {
+    let mut counted = Map::new();
+    for &((version_id, query_id), _timestamp, diff) in changes {
+        *counted.entry((query_id, version_id)).or_insert(0) += diff;
+    }
+
+    for (i, label) in labels.iter().enumerate() {
+        let mut rows: Vec<(&str, &Version)> = counted
+            .iter()
+            .filter(|&(&(query_id, _version_id), &count)| query_id.0 as usize == i && count > 0)
+            .map(|(&(_query_id, version_id), _count)| {
+                let (crate_id, version) = releases.get(version_id);
+                (crates.name(crate_id).unwrap(), version)
+            })
+            .collect();
+        rows.sort();
+
+        if i > 0 {
+            writeln!(out)?;
+        }
+        writeln!(out, "{} ({})", label, rows.len())?;
+        for (name, version) in rows {
+            writeln!(out, "    {} {}", name, version)?;
+        }
+    }
+    Ok(())
+}
+
+struct Transition {
+    timestamp: DateTime,
+    version_id: VersionId,
+    started: bool,
+}
+
+// For each query, the crates that were not counted at `since` but are by the
+// end of the changes, and the other way around, with the most recent instant
+// that each one crossed over and the release that made it happen.
+pub(crate) fn print_diff(
+    out: &mut dyn Write,
+    changes: &Changes,
+    since: DateTime,
+    releases: &Releases,
+    crates: &CrateMap,
+    labels: &[String],
+) -> io::Result<()> {
+    // Number of counted releases of each crate. Only one release of a crate is
+    // counted at a time, but while one replaces another both are in flight.
+    let mut counted = Map::<(QueryId, CrateId), isize>::new();
+    let mut initial = Map::<(QueryId, CrateId), bool>::new();
+    let mut transitions = Map::<(QueryId, CrateId), Transition>::new();
+
+    let mut i = 0;
+    while i < changes.len() {
+        let (_key, timestamp, _diff) = changes[i];
+        let mut before = Map::new();
+        let mut last_version = Map::new();
+        while let Some(&((version_id, query_id), t, diff)) = changes.get(i) {
+            if t != timestamp {
+                break;
+            }
+            let (crate_id, _version) = releases.get(version_id);
+            let key = (query_id, crate_id);
+            let count = counted.entry(key).or_insert(0);
+            before.entry(key).or_insert(*count > 0);
+            *count += diff;
+            if diff > 0 || !last_version.contains_key(&key) {
+                last_version.insert(key, version_id);
+            }
+            i += 1;
+        }
+        if timestamp < since {
+            continue;
+        }
+        for (key, was_counted) in before {
+            let is_counted = counted[&key] > 0;
+            if was_counted == is_counted {
+                continue;
+            }
+            if let Entry::Vacant(entry) = initial.entry(key) {
+                entry.insert(was_counted);
+            }
+            let transition = Transition {
+                timestamp,
+                version_id: last_version[&key],
+                started: is_counted,
+            };
+            transitions.insert(key, transition);
+        }
+    }
+
+    for (i, label) in labels.iter().enumerate() {
+        let mut started = Vec::new();
+        let mut stopped = Vec::new();
+        for (&(query_id, crate_id), transition) in &transitions {
+            if query_id.0 as usize != i || initial[&(query_id, crate_id)] == transition.started {
+                continue;
+            }
+            let name = crates.name(crate_id).unwrap();
+            if transition.started {
+                started.push((name, transition));
+            } else {
+                stopped.push((name, transition));
+            }
+        }
+
+        started.sort_by_key(|(name, transition)| (transition.timestamp, *name));
+        stopped.sort_by_key(|(name, transition)| (transition.timestamp, *name));
+
+        if i > 0 {
+            writeln!(out)?;
+        }
+        writeln!(
+            out,
+            "{} ({} started, {} stopped)",
+            label,
+            started.len(),
+            stopped.len(),
+        )?;
+        for (sign, rows) in [('+', started), ('-', stopped)] {
+            for (name, transition) in rows {
+                let (_crate_id, version) = releases.get(transition.version_id);
+                write!(
+                    out,
+                    "    {} {}  {} {}",
+                    sign,
+                    transition.timestamp.to_rfc3339(),
+                    name,
+                    version,
+                )?;
+                if let Some(&cause) = releases.by_time.get(&transition.timestamp) {
+                    let (crate_id, cause_version) = releases.get(cause);
+                    let cause_name = crates.name(crate_id).unwrap();
+                    if cause == transition.version_id {
+                        write!(out, "  (new release)")?;
+                    } else if cause_name == name {
+                        write!(out, "  (new release {})", cause_version)?;
+                    } else {
+                        write!(out, "  (release of {} {})", cause_name, cause_version)?;
+                    }
+                }
+                writeln!(out)?;
+            }
+        }
+    }
+    Ok(())
+}
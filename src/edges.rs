@@ -0,0 +1,234 @@
+// Edges of the feature-level dependency graph traversed in transitive mode,
+// pointing from a feature of a dependent release to a feature of a release
+// that it pulls in.
+
+use crate::arena::Slice;
+use crate::feature::{DefaultFeatures, FeatureEnables, FeatureId, FeatureIter, VersionFeature};
+use crate::id::{CrateId, VersionId};
+use crate::Release;
+use std::collections::BTreeSet as Set;
+use std::iter::once;
+
+// dependency edges arising from an entry under [dependencies]
+pub(crate) fn dependency(
+    version_id: VersionId,
+    feature_id: FeatureId,
+    default_features: DefaultFeatures,
+    features: Slice<FeatureId>,
+    resolved_version_id: VersionId,
+) -> impl Iterator<Item = (VersionFeature, VersionFeature)> {
+    let edge_from = VersionFeature {
+        version_id,
+        feature_id,
+    };
+    FeatureIter::new(default_features, features).map(move |feature_id| {
+        let edge_to = VersionFeature {
+            version_id: resolved_version_id,
+            feature_id,
+        };
+        (edge_from, edge_to)
+    })
+}
+
+// dependency edges from crate feature enabling other feature of same crate
+pub(crate) fn feature_intracrate(
+    rel: &Release,
+) -> impl Iterator<Item = (VersionFeature, VersionFeature)> {
+    let version_id = rel.id;
+    let crate_id = rel.crate_id;
+    rel.features
+        .into_iter()
+        .flat_map(move |feature| {
+            let edge_from = VersionFeature {
+                version_id,
+                feature_id: feature.id,
+            };
+            feature
+                .enables
+                .into_iter()
+                .filter_map(move |crate_feature| {
+                    if crate_feature.crate_id == crate_id {
+                        let edge_to = VersionFeature {
+                            version_id,
+                            feature_id: crate_feature.feature_id,
+                        };
+                        Some((edge_from, edge_to))
+                    } else {
+                        None
+                    }
+                })
+                .chain({
+                    if feature.id == FeatureId::DEFAULT {
+                        None
+                    } else {
+                        let edge_to = VersionFeature {
+                            version_id,
+                            feature_id: FeatureId::CRATE,
+                        };
+                        Some((edge_from, edge_to))
+                    }
+                })
+        })
+        .chain({
+            let edge_from = VersionFeature {
+                version_id,
+                feature_id: FeatureId::DEFAULT,
+            };
+            let edge_to = VersionFeature {
+                version_id,
+                feature_id: FeatureId::CRATE,
+            };
+            once((edge_from, edge_to))
+        })
+}
+
+// features of the release that turn on the given one, either by being it or by
+// enabling it through other features of the same crate
+pub(crate) fn enabling_features(rel: &Release, feature_id: FeatureId) -> Vec<FeatureId> {
+    let mut enabling = vec![feature_id];
+    let mut i = 0;
+    while let Some(&enabled) = enabling.get(i) {
+        for feature in rel.features {
+            if !enabling.contains(&feature.id)
+                && feature.enables.iter().any(|crate_feature| {
+                    crate_feature.crate_id == rel.crate_id && crate_feature.feature_id == enabled
+                })
+            {
+                enabling.push(feature.id);
+            }
+        }
+        i += 1;
+    }
+    enabling
+}
+
+// whether a dependency declared with these features turns on any of the given
+// features of the release it resolves to
+pub(crate) fn enables_any(
+    default_features: DefaultFeatures,
+    features: Slice<FeatureId>,
+    enabling: Slice<FeatureId>,
+) -> bool {
+    FeatureIter::new(default_features, features)
+        .any(|feature_id| enabling.iter().any(|enabling| enabling == feature_id))
+}
+
+// crate feature enabling feature of other crate, before resolving which release
+// of the other crate that refers to
+pub(crate) fn feature_dependency(
+    rel: &Release,
+) -> impl Iterator<Item = ((VersionId, CrateId), (FeatureId, FeatureId))> {
+    let version_id = rel.id;
+    let crate_id = rel.crate_id;
+    rel.features.into_iter().flat_map(move |feature| {
+        feature
+            .enables
+            .into_iter()
+            .filter_map(move |crate_feature| {
+                if crate_feature.crate_id == crate_id {
+                    None
+                } else {
+                    Some((
+                        (version_id, crate_feature.crate_id),
+                        (feature.id, crate_feature.feature_id),
+                    ))
+                }
+            })
+    })
+}
+
+// crate feature weakly enabling feature of other crate (`dep?/feature`). Cargo
+// only activates these if the dependency is enabled by something else, so the
+// edge is keyed by every feature whose dependency on the other crate makes the
+// weak enable take effect: FeatureId::CRATE for a non-optional dependency, or
+// any feature of the same crate transitively enabled by the weakly enabling
+// feature itself.
+//
+// An optional dependency enabled only by some other feature that a dependent
+// turns on alongside this one is covered by weak_feature_dependency_declared
+// instead, because edges of the graph are from one feature at a time.
+pub(crate) fn weak_feature_dependency(
+    rel: &Release,
+) -> impl Iterator<Item = ((VersionId, CrateId, FeatureId), (FeatureId, FeatureId))> {
+    let version_id = rel.id;
+    let crate_id = rel.crate_id;
+    let features = rel.features;
+    features
+        .into_iter()
+        .filter(|feature| !feature.weak_enables.is_empty())
+        .flat_map(move |feature| {
+            let enabled = enabled_features(crate_id, features, once(feature.id));
+            let mut edges = Vec::new();
+            for crate_feature in feature.weak_enables {
+                for &dep_feature_id in &enabled {
+                    edges.push((
+                        (version_id, crate_feature.crate_id, dep_feature_id),
+                        (feature.id, crate_feature.feature_id),
+                    ));
+                }
+            }
+            edges
+        })
+}
+
+// weak enables (`dep?/feature`) that take effect in a release when a dependent
+// declares its dependency on it with these features, taking into account all
+// of them together: with `std = ["serde?/std"]`, a dependent enabling both
+// "serde" and "std" gets serde's "std" feature although neither feature would
+// on its own. Keyed like weak_feature_dependency, by the other crate and every
+// feature whose dependency on it makes the weak enable take effect.
+pub(crate) fn weak_feature_dependency_declared(
+    rel: &Release,
+    default_features: DefaultFeatures,
+    features: Slice<FeatureId>,
+) -> Vec<((CrateId, FeatureId), FeatureId)> {
+    let enabled = enabled_features(
+        rel.crate_id,
+        rel.features,
+        FeatureIter::new(default_features, features),
+    );
+    let mut edges = Vec::new();
+    for feature in rel.features {
+        if !enabled.contains(&feature.id) {
+            continue;
+        }
+        for crate_feature in feature.weak_enables {
+            for &dep_feature_id in &enabled {
+                edges.push((
+                    (crate_feature.crate_id, dep_feature_id),
+                    crate_feature.feature_id,
+                ));
+            }
+        }
+    }
+    edges
+}
+
+// the given features of a release and every feature of the same crate that
+// they transitively enable, plus FeatureId::CRATE
+fn enabled_features(
+    crate_id: CrateId,
+    rel_features: Slice<FeatureEnables>,
+    features: impl IntoIterator<Item = FeatureId>,
+) -> Set<FeatureId> {
+    let mut enabled = Set::new();
+    let mut stack: Vec<FeatureId> = features.into_iter().collect();
+    while let Some(feature_id) = stack.pop() {
+        if !enabled.insert(feature_id) {
+            continue;
+        }
+        for feature in rel_features {
+            if feature.id == feature_id {
+                stack.extend(
+                    feature
+                        .enables
+                        .into_iter()
+                        .filter(|crate_feature| crate_feature.crate_id == crate_id)
+                        .map(|crate_feature| crate_feature.feature_id),
+                );
+            }
+        }
+    }
+    enabled.insert(FeatureId::CRATE);
+    enabled
+}
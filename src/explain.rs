@@ -0,0 +1,416 @@
+// Shortest path by which a crate comes to satisfy a term of a query, following
+// the same edges as `dataflow` but as of the end of the data only.
+
+use crate::arena::Slice;
+use crate::dependency::{DependencyKind, Optional};
+use crate::edges;
+use crate::feature::{FeatureId, FeatureIter, VersionFeature};
+use crate::id::{CrateId, VersionId};
+use crate::version::VersionReq;
+use crate::{Config, DbDump, Dependency, Predicate, Release};
+use std::collections::btree_map::Entry;
+use std::collections::{BTreeMap as Map, VecDeque};
+
+#[derive(Copy, Clone, Debug)]
+pub struct Edge {
+    pub from: VersionFeature,
+    pub to: VersionFeature,
+    pub kind: EdgeKind,
+}
+
+#[derive(Copy, Clone, Debug)]
+pub enum EdgeKind {
+    // An entry in the dependent's [dependencies], [build-dependencies] or
+    // [dev-dependencies].
+    Dependency(DependencyKind),
+    // A feature of a crate enabling another feature of the same crate.
+    FeatureIntracrate,
+    // A feature of a crate enabling a feature of one of its dependencies.
+    FeatureDependency,
+    // A dependent turning on features of the given release which together
+    // enable a feature of one of its dependencies by way of `dep?/feature`.
+    WeakFeatureDependency(VersionId),
+}
+
+pub enum Explanation {
+    // The dependent's most recent release itself matches the term.
+    Matches(VersionId),
+    // Path from a feature of the dependent's most recent release to a release
+    // that matches the term.
+    Path(Vec<Edge>),
+}
+
+pub fn explain(
+    db_dump: &DbDump,
+    config: Config,
+    term: Slice<Predicate>,
+    dependent: CrateId,
+) -> Option<Explanation> {
+    if config.optional == Optional::Only {
+        let without_optional = Config {
+            optional: Optional::Exclude,
+            ..config
+        };
+        if explain(db_dump, without_optional, term, dependent).is_some() {
+            return None;
+        }
+        let with_optional = Config {
+            optional: Optional::Include,
+            ..config
+        };
+        return explain(db_dump, with_optional, term, dependent);
+    }
+
+    let graph = Graph::new(db_dump, config);
+
+    let latest = graph
+        .releases_by_crate_id
+        .get(&dependent)?
+        .iter()
+        .max_by_key(|rel| (rel.num.pre.is_empty(), rel.created_at, rel.id))?;
+    let satisfies = |pred: &Predicate, version_id: VersionId| {
+        let rel = graph.releases[&version_id];
+        pred.crate_id == rel.crate_id
+            && match pred.req {
+                None => true,
+                Some(req) => req.matches(&rel.num),
+            }
+    };
+    // Whether the release satisfies a predicate that does not restrict the
+    // dependency on it.
+    let matches = |version_id: VersionId| {
+        term.iter_ref()
+            .any(|pred| !pred.restricts_dependency() && satisfies(pred, version_id))
+    };
+    // Whether reaching this feature of a release satisfies the term.
+    let matches_feature = |node: VersionFeature| {
+        term.iter_ref().any(|pred| {
+            !pred.restricts_dependency()
+                && pred.feature.unwrap_or(FeatureId::CRATE) == node.feature_id
+                && satisfies(pred, node.version_id)
+        })
+    };
+    // Edges by which a dependency satisfies the term: onto the release it
+    // resolves to, and within that release on to the feature named by the
+    // predicate, if any. Predicates that do not restrict the dependency are
+    // only considered for `direct` dependencies.
+    let matches_dependency =
+        |from: VersionFeature, dep: &Dependency, resolved: VersionId, direct: bool| {
+            let rel = graph.releases[&resolved];
+            for pred in term.iter_ref() {
+                let kinds = match pred.kinds {
+                    Some(kinds) => kinds,
+                    None if direct => config.direct_kinds,
+                    None if pred.restricts_dependency() => config.transitive_kinds,
+                    None => continue,
+                };
+                if !kinds.contains(dep.kind)
+                    || pred
+                        .default_features
+                        .is_some_and(|keeps| keeps != dep.default_features)
+                    || !satisfies(pred, resolved)
+                {
+                    continue;
+                }
+                let feature_id = pred.feature.unwrap_or(FeatureId::CRATE);
+                let enabled = if feature_id == FeatureId::CRATE {
+                    FeatureId::CRATE
+                } else {
+                    let enabling = edges::enabling_features(rel, feature_id);
+                    let enabled = FeatureIter::new(dep.default_features, dep.features)
+                        .find(|feature_id| enabling.contains(feature_id));
+                    match enabled {
+                        Some(enabled) => enabled,
+                        None => continue,
+                    }
+                };
+                let to = VersionFeature {
+                    version_id: resolved,
+                    feature_id: enabled,
+                };
+                let mut path = vec![Edge {
+                    from,
+                    to,
+                    kind: EdgeKind::Dependency(dep.kind),
+                }];
+                path.extend(intracrate_path(rel, enabled, feature_id));
+                return Some(path);
+            }
+            None
+        };
+
+    for dep in graph.dependencies(latest.id) {
+        let from = VersionFeature {
+            version_id: latest.id,
+            feature_id: dep.feature_id,
+        };
+        if let Some(resolved) = graph.resolve(dep.crate_id, dep.req) {
+            if let Some(path) = matches_dependency(from, dep, resolved, true) {
+                return Some(Explanation::Path(path));
+            }
+        }
+    }
+
+    if !config.transitive {
+        return None;
+    }
+
+    if matches(latest.id) {
+        return Some(Explanation::Matches(latest.id));
+    }
+
+    // Breadth-first search starting from every feature of the dependent.
+    let mut adjacency = Map::<VersionId, Vec<Edge>>::new();
+    let mut parent = Map::<VersionFeature, Option<Edge>>::new();
+    let mut queue = VecDeque::new();
+    for edge in graph.edges(latest.id) {
+        if let Entry::Vacant(entry) = parent.entry(edge.from) {
+            entry.insert(None);
+            queue.push_back(edge.from);
+        }
+    }
+    while let Some(node) = queue.pop_front() {
+        // Predicates that restrict the dependency are matched by the dependency
+        // itself, not by the release it resolves to.
+        let mut last = Vec::new();
+        for dep in graph.dependencies(node.version_id) {
+            if dep.feature_id != node.feature_id {
+                continue;
+            }
+            let Some(resolved) = graph.resolve(dep.crate_id, dep.req) else {
+                continue;
+            };
+            if let Some(path) = matches_dependency(node, dep, resolved, false) {
+                last = path;
+                break;
+            }
+        }
+        if !last.is_empty() || matches_feature(node) {
+            let mut path = Vec::new();
+            let mut node = node;
+            while let Some(edge) = parent[&node] {
+                path.push(edge);
+                node = edge.from;
+            }
+            path.reverse();
+            path.extend(last);
+            return Some(Explanation::Path(path));
+        }
+        let edges = adjacency
+            .entry(node.version_id)
+            .or_insert_with(|| graph.edges(node.version_id));
+        for &edge in edges.iter() {
+            if edge.from != node {
+                continue;
+            }
+            if let Entry::Vacant(entry) = parent.entry(edge.to) {
+                entry.insert(Some(edge));
+                queue.push_back(edge.to);
+            }
+        }
+    }
+
+    None
+}
+
+// Shortest chain of features of the release, each enabling the next, from one
+// feature to another that it turns on.
+fn intracrate_path(rel: &Release, from: FeatureId, to: FeatureId) -> Vec<Edge> {
+    let mut parent = Map::<FeatureId, Option<Edge>>::new();
+    parent.insert(from, None);
+    let mut queue = VecDeque::from([from]);
+    while let Some(feature_id) = queue.pop_front() {
+        if feature_id == to {
+            let mut path = Vec::new();
+            let mut feature_id = feature_id;
+            while let Some(edge) = parent[&feature_id] {
+                path.push(edge);
+                feature_id = edge.from.feature_id;
+            }
+            path.reverse();
+            return path;
+        }
+        for (edge_from, edge_to) in edges::feature_intracrate(rel) {
+            if edge_from.feature_id != feature_id {
+                continue;
+            }
+            if let Entry::Vacant(entry) = parent.entry(edge_to.feature_id) {
+                entry.insert(Some(Edge {
+                    from: edge_from,
+                    to: edge_to,
+                    kind: EdgeKind::FeatureIntracrate,
+                }));
+                queue.push_back(edge_to.feature_id);
+            }
+        }
+    }
+    Vec::new()
+}
+
+struct Graph<'a> {
+    config: Config,
+    releases: Map<VersionId, &'a Release>,
+    releases_by_crate_id: Map<CrateId, Vec<&'a Release>>,
+    dependencies_by_version_id: Map<VersionId, Vec<&'a Dependency>>,
+}
+
+impl<'a> Graph<'a> {
+    fn new(db_dump: &'a DbDump, config: Config) -> Self {
+        let mut releases = Map::new();
+        let mut releases_by_crate_id = Map::<CrateId, Vec<&Release>>::new();
+        for rel in &db_dump.releases {
+            releases.insert(rel.id, rel);
+            releases_by_crate_id
+                .entry(rel.crate_id)
+                .or_default()
+                .push(rel);
+        }
+        let mut dependencies_by_version_id = Map::<VersionId, Vec<&Dependency>>::new();
+        for dep in &db_dump.dependencies {
+            if config.optional == Optional::Exclude && dep.feature_id != FeatureId::CRATE {
+                continue;
+            }
+            dependencies_by_version_id
+                .entry(dep.version_id)
+                .or_default()
+                .push(dep);
+        }
+        Graph {
+            config,
+            releases,
+            releases_by_crate_id,
+            dependencies_by_version_id,
+        }
+    }
+
+    fn dependencies(&self, version_id: VersionId) -> impl Iterator<Item = &'a Dependency> + '_ {
+        self.dependencies_by_version_id
+            .get(&version_id)
+            .into_iter()
+            .flatten()
+            .copied()
+    }
+
+    // Greatest release of the crate matching the requirement.
+    fn resolve(&self, crate_id: CrateId, req: VersionReq) -> Option<VersionId> {
+        self.releases_by_crate_id
+            .get(&crate_id)?
+            .iter()
+            .filter(|rel| req.matches(&rel.num))
+            .max_by_key(|rel| &rel.num)
+            .map(|rel| rel.id)
+    }
+
+    // All edges out of any feature of the given release.
+    fn edges(&self, version_id: VersionId) -> Vec<Edge> {
+        let mut edges = Vec::new();
+        let Some(rel) = self.releases.get(&version_id) else {
+            return edges;
+        };
+
+        for dep in self.dependencies(version_id) {
+            if !self.config.transitive_kinds.contains(dep.kind) {
+                continue;
+            }
+            let Some(resolved) = self.resolve(dep.crate_id, dep.req) else {
+                continue;
+            };
+            edges.extend(
+                edges::dependency(
+                    dep.version_id,
+                    dep.feature_id,
+                    dep.default_features,
+                    dep.features,
+                    resolved,
+                )
+                .map(|(from, to)| Edge {
+                    from,
+                    to,
+                    kind: EdgeKind::Dependency(dep.kind),
+                }),
+            );
+            let Some(resolved_rel) = self.releases.get(&resolved) else {
+                continue;
+            };
+            for ((crate_id, dep_feature_id), to_feature) in edges::weak_feature_dependency_declared(
+                resolved_rel,
+                dep.default_features,
+                dep.features,
+            ) {
+                for weak_dep in self.dependencies(resolved) {
+                    if weak_dep.crate_id != crate_id || weak_dep.feature_id != dep_feature_id {
+                        continue;
+                    }
+                    let Some(weak_resolved) = self.resolve(weak_dep.crate_id, weak_dep.req) else {
+                        continue;
+                    };
+                    edges.push(Edge {
+                        from: VersionFeature {
+                            version_id: dep.version_id,
+                            feature_id: dep.feature_id,
+                        },
+                        to: VersionFeature {
+                            version_id: weak_resolved,
+                            feature_id: to_feature,
+                        },
+                        kind: EdgeKind::WeakFeatureDependency(resolved),
+                    });
+                }
+            }
+        }
+
+        edges.extend(edges::feature_intracrate(rel).map(|(from, to)| Edge {
+            from,
+            to,
+            kind: EdgeKind::FeatureIntracrate,
+        }));
+
+        for ((version_id, crate_id), (from_feature, to_feature)) in edges::feature_dependency(rel) {
+            for dep in self.dependencies(version_id) {
+                if dep.crate_id != crate_id {
+                    continue;
+                }
+                let Some(resolved) = self.resolve(dep.crate_id, dep.req) else {
+                    continue;
+                };
+                edges.push(Edge {
+                    from: VersionFeature {
+                        version_id,
+                        feature_id: from_feature,
+                    },
+                    to: VersionFeature {
+                        version_id: resolved,
+                        feature_id: to_feature,
+                    },
+                    kind: EdgeKind::FeatureDependency,
+                });
+            }
+        }
+
+        for ((version_id, crate_id, dep_feature_id), (from_feature, to_feature)) in
+            edges::weak_feature_dependency(rel)
+        {
+            for dep in self.dependencies(version_id) {
+                if dep.crate_id != crate_id || dep.feature_id != dep_feature_id {
+                    continue;
+                }
+                let Some(resolved) = self.resolve(dep.crate_id, dep.req) else {
+                    continue;
+                };
+                edges.push(Edge {
+                    from: VersionFeature {
+                        version_id,
+                        feature_id: from_feature,
+                    },
+                    to: VersionFeature {
+                        version_id: resolved,
+                        feature_id: to_feature,
+                    },
+                    kind: EdgeKind::FeatureDependency,
+                });
+            }
+        }
+
+        edges
+    }
+}
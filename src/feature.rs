@@ -61,6 +61,10 @@ impl FeatureNames {
         }
     }
 
+    pub fn get(&self, name: &str) -> Option<FeatureId> {
+        self.map.get(name).copied()
+    }
+
     pub fn name(&self, id: FeatureId) -> &str {
         &self.names[id.0 as usize]
     }
@@ -0,0 +1,291 @@
+//! Machine-readable renderings of the tally matrix: a time column in RFC 3339
+//! followed by one column per query, labeled the same as in the graph legend.
+
+use crate::total::Total;
+use cargo_tally::matrix::Matrix;
+use cargo_tally::timestamp::DateTime;
+use std::fmt::{self, Display, Write as _};
+use std::io::{self, Write};
+use std::str::FromStr;
+
+#[derive(Copy, Clone, Debug)]
+pub(crate) enum Format {
+    Csv,
+    Tsv,
+    Json,
+    Ndjson,
+    Svg,
+}
+
+impl Format {
+    pub(crate) const NAMES: [&'static str; 5] = ["csv", "tsv", "json", "ndjson", "svg"];
+}
+
+impl FromStr for Format {
+    type Err = ();
+
+    fn from_str(string: &str) -> Result<Self, Self::Err> {
+        match string {
+            "csv" => Ok(Format::Csv),
+            "tsv" => Ok(Format::Tsv),
+            "json" => Ok(Format::Json),
+            "ndjson" => Ok(Format::Ndjson),
+            "svg" => Ok(Format::Svg),
+            _ => Err(()),
+        }
+    }
+}
+
+pub(crate) fn write(
+    out: &mut dyn Write,
+    format: Format,
+    results: &Matrix,
+    labels: &[String],
+    total: Option<&Total>,
+) -> io::Result<()> {
+    match format {
+        Format::Csv => write_delimited(out, ',', results, labels, total),
+        Format::Tsv => write_delimited(out, '\t', results, labels, total),
+        Format::Json => write_json(out, results, labels, total),
+        Format::Ndjson => write_ndjson(out, results, labels, total),
+        Format::Svg => unreachable!(),
+    }
+}
+
+fn write_delimited(
+    out: &mut dyn Write,
+    delimiter: char,
+    results: &Matrix,
+    labels: &[String],
+    total: Option<&Total>,
+) -> io::Result<()> {
+    out.write_all(b"time")?;
+    for label in labels {
+        write!(out, "{}", delimiter)?;
+        if delimiter == ',' {
+            write_csv_field(out, label)?;
+        } else {
+            write_tsv_field(out, label)?;
+        }
+    }
+    out.write_all(b"\n")?;
+
+    for (timestamp, row) in results {
+        out.write_all(timestamp.to_rfc3339().as_bytes())?;
+        for value in row {
+            write!(out, "{}{}", delimiter, Value::new(value, timestamp, total))?;
+        }
+        out.write_all(b"\n")?;
+    }
+    Ok(())
+}
+
+fn write_json(
+    out: &mut dyn Write,
+    results: &Matrix,
+    labels: &[String],
+    total: Option<&Total>,
+) -> io::Result<()> {
+    out.write_all(b"{\"columns\":[\"time\"")?;
+    for label in labels {
+        out.write_all(b",")?;
+        write_json_string(out, label)?;
+    }
+    out.write_all(b"],\"data\":[")?;
+    for (i, (timestamp, row)) in results.iter().enumerate() {
+        if i > 0 {
+            out.write_all(b",")?;
+        }
+        out.write_all(b"\n[")?;
+        write_json_string(out, &timestamp.to_rfc3339())?;
+        for value in row {
+            write!(out, ",{}", Value::new(value, timestamp, total))?;
+        }
+        out.write_all(b"]")?;
+    }
+    out.write_all(b"\n]}\n")?;
+    Ok(())
+}
+
+fn write_ndjson(
+    out: &mut dyn Write,
+    results: &Matrix,
+    labels: &[String],
+    total: Option<&Total>,
+) -> io::Result<()> {
+    for (timestamp, row) in results {
+        out.write_all(b"{\"time\":")?;
+        write_json_string(out, &timestamp.to_rfc3339())?;
+        for (label, value) in labels.iter().zip(row) {
+            out.write_all(b",")?;
+            write_json_string(out, label)?;
+            write!(out, ":{}", Value::new(value, timestamp, total))?;
+        }
+        out.write_all(b"}\n")?;
+    }
+    Ok(())
+}
+
+fn write_csv_field(out: &mut dyn Write, field: &str) -> io::Result<()> {
+    if field.contains([',', '"', '\n', '\r']) {
+        write!(out, "\"{}\"", field.replace('"', "\"\""))
+    } else {
+        out.write_all(field.as_bytes())
+    }
+}
+
+// TSV has no quoting, so tabs and line breaks inside a field are written as
+// backslash escapes, the same as in PostgreSQL's text format.
+fn write_tsv_field(out: &mut dyn Write, field: &str) -> io::Result<()> {
+    if field.contains(['\t', '\n', '\r', '\\']) {
+        let escaped = field
+            .replace('\\', "\\\\")
+            .replace('\t', "\\t")
+            .replace('\n', "\\n")
+            .replace('\r', "\\r");
+        out.write_all(escaped.as_bytes())
+    } else {
+        out.write_all(field.as_bytes())
+    }
+}
+
+fn write_json_string(out: &mut dyn Write, string: &str) -> io::Result<()> {
+    write!(out, "{}", JsonString(string))
+}
+
+// A string written as a JSON string literal, with quotes.
+pub(crate) struct JsonString<'a>(pub &'a str);
+
+impl<'a> Display for JsonString<'a> {
+    fn fmt(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        formatter.write_str("\"")?;
+        for ch in self.0.chars() {
+            match ch {
+                '"' => formatter.write_str("\\\"")?,
+                '\\' => formatter.write_str("\\\\")?,
+                '\n' => formatter.write_str("\\n")?,
+                '\r' => formatter.write_str("\\r")?,
+                '\t' => formatter.write_str("\\t")?,
+                ch if ch < ' ' => write!(formatter, "\\u{:04x}", ch as u32)?,
+                ch => formatter.write_char(ch)?,
+            }
+        }
+        formatter.write_str("\"")
+    }
+}
+
+enum Value {
+    Absolute(u32),
+    Relative(f64),
+}
+
+impl Value {
+    fn new(value: u32, timestamp: DateTime, total: Option<&Total>) -> Self {
+        match total {
+            None => Value::Absolute(value),
+            Some(total) => {
+                let total = total.eval(timestamp);
+                if total == 0 {
+                    Value::Relative(0.0)
+                } else {
+                    Value::Relative(f64::from(value) / f64::from(total))
+                }
+            }
+        }
+    }
+}
+
+impl Display for Value {
+    fn fmt(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Value::Absolute(value) => Display::fmt(value, formatter),
+            Value::Relative(fraction) => Display::fmt(fraction, formatter),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{write, Format};
+    use crate::total::Total;
+    use cargo_tally::matrix::Matrix;
+    use cargo_tally::timestamp::DateTime;
+    use chrono::{NaiveDate, NaiveTime};
+
+    fn day(day: u32) -> DateTime {
+        DateTime::new(
+            NaiveDate::from_ymd_opt(2022, 1, day).unwrap(),
+            NaiveTime::MIN,
+        )
+    }
+
+    // One query with 1 dependent on the 2nd and 2 on the 3rd, out of one
+    // crate existing on the 1st, two on the 2nd and three on the 3rd.
+    fn render(format: Format, label: &str, relative: bool) -> String {
+        let mut results = Matrix::new(1);
+        results.push(day(2), [(0, 1)]);
+        results.push(day(3), [(0, 2)]);
+        let total = Total::from_times(vec![day(1), day(2), day(3)]);
+        let total = relative.then_some(&total);
+        let mut out = Vec::new();
+        write(&mut out, format, &results, &[label.to_owned()], total).unwrap();
+        String::from_utf8(out).unwrap()
+    }
+
+    const LABEL: &str = "serde, \"1.0\"";
+
+    #[test]
+    fn test_csv() {
+        let expected = "\
+            time,\"serde, \"\"1.0\"\"\"\n\
+            2022-01-02T00:00:00Z,1\n\
+            2022-01-03T00:00:00Z,2\n";
+        assert_eq!(render(Format::Csv, LABEL, false), expected);
+
+        let expected = "\
+            time,serde\n\
+            2022-01-02T00:00:00Z,0.5\n\
+            2022-01-03T00:00:00Z,0.6666666666666666\n";
+        assert_eq!(render(Format::Csv, "serde", true), expected);
+    }
+
+    #[test]
+    fn test_tsv() {
+        let expected = "\
+            time\tserde\n\
+            2022-01-02T00:00:00Z\t1\n\
+            2022-01-03T00:00:00Z\t2\n";
+        assert_eq!(render(Format::Tsv, "serde", false), expected);
+
+        let expected = "\
+            time\tre:/a\\tb\\nc\\\\d/\n\
+            2022-01-02T00:00:00Z\t1\n\
+            2022-01-03T00:00:00Z\t2\n";
+        assert_eq!(render(Format::Tsv, "re:/a\tb\nc\\d/", false), expected);
+    }
+
+    #[test]
+    fn test_json() {
+        let expected = "\
+            {\"columns\":[\"time\",\"serde, \\\"1.0\\\"\"],\"data\":[\n\
+            [\"2022-01-02T00:00:00Z\",1],\n\
+            [\"2022-01-03T00:00:00Z\",2]\n\
+            ]}\n";
+        assert_eq!(render(Format::Json, LABEL, false), expected);
+
+        let expected = "\
+            {\"columns\":[\"time\",\"serde\"],\"data\":[\n\
+            [\"2022-01-02T00:00:00Z\",0.5],\n\
+            [\"2022-01-03T00:00:00Z\",0.6666666666666666]\n\
+            ]}\n";
+        assert_eq!(render(Format::Json, "serde", true), expected);
+    }
+
+    #[test]
+    fn test_ndjson() {
+        let expected = "\
+            {\"time\":\"2022-01-02T00:00:00Z\",\"serde, \\\"1.0\\\"\":1}\n\
+            {\"time\":\"2022-01-03T00:00:00Z\",\"serde, \\\"1.0\\\"\":2}\n";
+        assert_eq!(render(Format::Ndjson, LABEL, false), expected);
+    }
+}
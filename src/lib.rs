@@ -34,6 +34,8 @@ pub mod arena;
 pub(crate) mod collect;
 mod communication;
 pub mod dependency;
+pub(crate) mod edges;
+pub mod explain;
 pub mod feature;
 pub(crate) mod hint;
 pub mod id;
@@ -45,13 +47,11 @@ pub mod timestamp;
 pub mod version;
 
 use crate::arena::Slice;
-use crate::collect::{Collect, Emitter, ResultCollection};
-use crate::dependency::DependencyKind;
-use crate::feature::{
-    DefaultFeatures, FeatureEnables, FeatureId, FeatureIter, FeatureNames, VersionFeature,
-};
+use crate::collect::{Collect, ResultCollection};
+use crate::dependency::{DependencyKind, DependencyKinds, Optional};
+use crate::feature::{DefaultFeatures, FeatureEnables, FeatureId, FeatureNames, VersionFeature};
 use crate::hint::TypeHint;
-use crate::id::{CrateId, DependencyId, QueryId, VersionId};
+use crate::id::{CrateId, DependencyId, QueryId, TermId, VersionId};
 use crate::matrix::Matrix;
 use crate::max::MaxByKey;
 use crate::present::Present;
@@ -61,9 +61,11 @@ use atomic_take::AtomicTake;
 use differential_dataflow::input::InputSession;
 use differential_dataflow::operators::arrange::{ArrangeByKey, ArrangeBySelf};
 use differential_dataflow::operators::iterate::Variable;
-use differential_dataflow::operators::{Join, JoinCore, Threshold};
+use differential_dataflow::operators::{Join, JoinCore, Reduce, Threshold};
+use std::collections::BTreeSet as Set;
 use std::env;
 use std::iter::once;
+use std::mem;
 use std::net::TcpStream;
 use std::ops::Deref;
 use timely::communication::allocator::Process;
@@ -106,13 +108,84 @@ pub struct Dependency {
 #[derive(Copy, Clone, Debug)]
 pub struct Query {
     pub id: QueryId,
-    pub predicates: Slice<Predicate>,
+    // Groups of predicates. A crate satisfies a term if it depends on anything
+    // matching one of the term's predicates.
+    pub terms: Slice<Slice<Predicate>>,
+    // Which combinations of satisfied terms count toward the query, in postfix
+    // order.
+    pub expr: Slice<Op>,
+    // In transitive mode, how many dependencies away from a crate matching the
+    // query a dependent can be and still count. None for no limit.
+    pub depth: Option<u16>,
+}
+
+#[derive(Copy, Clone, Ord, PartialOrd, Eq, PartialEq, Hash, Debug)]
+pub enum Op {
+    Term(TermId),
+    Not,
+    And,
+    Or,
+}
+
+// Evaluates a query's expression given which of its terms are satisfied.
+pub fn eval(expr: Slice<Op>, satisfied: impl Fn(TermId) -> bool) -> bool {
+    let mut stack = Vec::<bool>::new();
+    for op in expr {
+        let value = match op {
+            Op::Term(term_id) => satisfied(term_id),
+            Op::Not => !stack.pop().unwrap(),
+            Op::And => {
+                let rhs = stack.pop().unwrap();
+                let lhs = stack.pop().unwrap();
+                lhs && rhs
+            }
+            Op::Or => {
+                let rhs = stack.pop().unwrap();
+                let lhs = stack.pop().unwrap();
+                lhs || rhs
+            }
+        };
+        stack.push(value);
+    }
+    stack.pop().unwrap()
 }
 
 #[derive(Copy, Clone, Debug)]
 pub struct Predicate {
     pub crate_id: CrateId,
     pub req: Option<VersionReq>,
+    // Kinds of dependency on the matching crate that count, in place of
+    // Config::direct_kinds.
+    pub kinds: Option<DependencyKinds>,
+    // Feature of the matching crate that a dependency must turn on, whether by
+    // name or through another feature that enables it.
+    pub feature: Option<FeatureId>,
+    // Whether a dependency on the matching crate must keep its default features
+    // turned on, or turn them off, in order to count.
+    pub default_features: Option<DefaultFeatures>,
+}
+
+impl Predicate {
+    // Whether the predicate is on the dependency itself, rather than only on
+    // which release it resolves to.
+    pub(crate) fn restricts_dependency(&self) -> bool {
+        self.kinds.is_some() || self.default_features.is_some()
+    }
+}
+
+#[derive(Copy, Clone, Debug)]
+pub struct Config {
+    pub transitive: bool,
+    // Kinds of dependency by which a crate counts toward a query it depends on
+    // directly.
+    pub direct_kinds: DependencyKinds,
+    // Kinds of dependency followed from one crate to the next in transitive
+    // mode.
+    pub transitive_kinds: DependencyKinds,
+    pub optional: Optional,
+    // Count the dependencies of the crates matching each query, rather than
+    // their dependents.
+    pub reverse: bool,
 }
 
 #[derive(Default)]
@@ -121,12 +194,70 @@ struct Input {
     queries: Vec<Query>,
 }
 
-pub fn run(db_dump: DbDump, jobs: usize, transitive: bool, queries: &[Query]) -> Matrix {
+pub fn run(db_dump: DbDump, jobs: usize, config: Config, queries: &[Query]) -> Matrix {
     let num_queries = queries.len();
-    let queries = queries.to_owned();
-    let input = AtomicTake::new(Input { db_dump, queries });
     let collection = ResultCollection::<(QueryId, DateTime, isize)>::new();
     let results = collection.emitter();
+    execute(db_dump, jobs, config, queries, move |query_results| {
+        query_results
+            .map(|(_version_id, query_id)| query_id)
+            .consolidate()
+            .collect_into(&results);
+    });
+
+    let mut time = DateTime::minimum();
+    let mut values = vec![0u32; num_queries];
+    let mut touched = Set::new();
+    let mut matrix = Matrix::new(num_queries);
+    collection.sort();
+    for (query_id, timestamp, diff) in collection {
+        if timestamp > time {
+            if !touched.is_empty() {
+                let changes = mem::take(&mut touched).into_iter();
+                matrix.push(time, changes.map(|i| (i, values[i])));
+            }
+            time = timestamp;
+        }
+        let i = query_id.0 as usize;
+        let cell = &mut values[i];
+        if diff > 0 {
+            *cell += diff as u32;
+        } else {
+            *cell = cell.checked_sub(-diff as u32).expect("value went negative");
+        }
+        touched.insert(i);
+    }
+    if !touched.is_empty() {
+        matrix.push(time, touched.into_iter().map(|i| (i, values[i])));
+    }
+    matrix
+}
+
+// Every change to which releases are counted toward which query, up to but not
+// including the instant `until`, in chronological order.
+pub fn dependents(
+    mut db_dump: DbDump,
+    jobs: usize,
+    config: Config,
+    queries: &[Query],
+    until: DateTime,
+) -> Vec<((VersionId, QueryId), DateTime, isize)> {
+    db_dump.releases.retain(|rel| rel.created_at < until);
+    let collection = ResultCollection::<((VersionId, QueryId), DateTime, isize)>::new();
+    let results = collection.emitter();
+    execute(db_dump, jobs, config, queries, move |query_results| {
+        query_results.consolidate().collect_into(&results);
+    });
+    collection.sort();
+    collection.into_iter().collect()
+}
+
+fn execute<F>(db_dump: DbDump, jobs: usize, config: Config, queries: &[Query], output: F)
+where
+    F: for<'a> Fn(stream![VersionId => QueryId; isize]) + Send + Sync + 'static,
+{
+    let queries = queries.to_owned();
+    let input = AtomicTake::new(Input { db_dump, queries });
 
     let allocators = Process::new_vector(jobs);
     let other = Box::new(());
@@ -139,14 +270,13 @@ pub fn run(db_dump: DbDump, jobs: usize, transitive: bool, queries: &[Query]) ->
         let mut dependencies = InputSession::<DateTime, Dependency, Present>::new();
 
         worker.dataflow(|scope| {
-            dataflow(
+            output(dataflow(
                 scope,
                 &mut queries,
                 &mut releases,
                 &mut dependencies,
-                transitive,
-                &results,
-            );
+                config,
+            ));
         });
 
         let input = input.take().unwrap_or_default();
@@ -170,32 +300,6 @@ pub fn run(db_dump: DbDump, jobs: usize, transitive: bool, queries: &[Query]) ->
         while worker.step_or_park(None) {}
     })
     .unwrap();
-
-    let mut time = DateTime::minimum();
-    let mut values = vec![0u32; num_queries];
-    let mut matrix = Matrix::new(num_queries);
-    collection.sort();
-    for (i, (query_id, timestamp, diff)) in collection.into_iter().enumerate() {
-        if timestamp > time {
-            if i > 0 {
-                matrix.push(time, values.clone());
-            }
-            time = timestamp;
-        }
-        let cell = &mut values[query_id.0 as usize];
-        if diff > 0 {
-            *cell += diff as u32;
-        } else {
-            *cell = cell.checked_sub(-diff as u32).expect("value went negative");
-        }
-    }
-    if match matrix.iter().next_back() {
-        Some((_timestamp, last)) => values != **last,
-        None => values.iter().any(|&n| n != 0),
-    } {
-        matrix.push(time, values);
-    }
-    matrix
 }
 
 fn set_timely_worker_log(worker: &Worker<Process>) {
@@ -218,14 +322,15 @@ fn set_timely_worker_log(worker: &Worker<Process>) {
     });
 }
 
-fn dataflow(
-    scope: &mut Child<Worker<Process>, DateTime>,
+// Releases that are counted toward each query: the most recent release of
+// every crate that depends on something matching the query.
+fn dataflow<'a>(
+    scope: &mut Child<'a, Worker<Process>, DateTime>,
     queries: &mut InputSession<DateTime, Query, Present>,
     releases: &mut InputSession<DateTime, Release, Present>,
     dependencies: &mut InputSession<DateTime, Dependency, Present>,
-    transitive: bool,
-    results: &Emitter<(QueryId, DateTime, isize)>,
-) {
+    config: Config,
+) -> stream![VersionId => QueryId; isize] {
     type queries<'a> = stream![Query; Present];
     let queries: queries = queries.to_collection(scope);
 
@@ -235,39 +340,75 @@ fn dataflow(
     type dependencies<'a> = stream![Dependency; Present];
     let dependencies: dependencies = dependencies.to_collection(scope);
 
-    // the version ids and version numbers that exist of each crate
-    type releases_by_crate_id<'a> = stream![CrateId => (VersionId, Version); Present];
-    let releases_by_crate_id: releases_by_crate_id =
-        releases.map(|rel| (rel.crate_id, (rel.id, rel.num)));
+    // dependencies that are not behind a feature of the dependent
+    let required_dependencies = || dependencies.filter(|dep| dep.feature_id == FeatureId::CRATE);
+
+    match config.optional {
+        Optional::Include => tally(scope, &queries, &releases, &dependencies, config),
+        Optional::Exclude => tally(scope, &queries, &releases, &required_dependencies(), config),
+        Optional::Only => {
+            // two whole tallies, which in transitive mode is twice the memory
+            let with_optional = tally(scope, &queries, &releases, &dependencies, config);
+            let without_optional =
+                tally(scope, &queries, &releases, &required_dependencies(), config);
+            with_optional.concat(&without_optional.negate())
+        }
+    }
+}
+
+// Releases counted toward each query if these are all the dependencies there
+// are.
+fn tally<'a>(
+    scope: &mut Child<'a, Worker<Process>, DateTime>,
+    queries: &stream![Query; Present],
+    releases: &stream![Release; Present],
+    dependencies: &stream![Dependency; Present],
+    config: Config,
+) -> stream![VersionId => QueryId; isize] {
+    // the releases that exist of each crate
+    type releases_by_crate_id<'a> = stream![CrateId => Release; Present];
+    let releases_by_crate_id: releases_by_crate_id = releases.map(|rel| (rel.crate_id, rel));
     let releases_by_crate_id = releases_by_crate_id.arrange_by_key();
 
+    // every release by its id
+    type releases_by_version_id<'a> = stream![VersionId => Release; Present];
+    let releases_by_version_id: releases_by_version_id = releases.map(|rel| (rel.id, rel));
+    let releases_by_version_id = releases_by_version_id.arrange_by_key();
+
     // for each dependency spec, what release does it refer to currently?
     type resolved<'a> = stream![(CrateId, VersionReq) => VersionId; isize];
     let resolved: resolved = dependencies
         .map(|dep| (dep.crate_id, dep.req))
         .KV::<CrateId, VersionReq>()
-        .join_core(
-            &releases_by_crate_id,
-            |crate_id, req, (version_id, version)| {
-                req.matches(version)
-                    .then(|| ((*crate_id, *req), (version.clone(), *version_id)))
-            },
-        )
+        .join_core(&releases_by_crate_id, |crate_id, req, rel| {
+            req.matches(&rel.num)
+                .then(|| ((*crate_id, *req), (rel.num.clone(), rel.id)))
+        })
         .KV::<(CrateId, VersionReq), (Version, VersionId)>()
         .max_by_key()
         .KV::<(CrateId, VersionReq), (Version, VersionId)>()
         .map(|((crate_id, req), (_version, version_id))| ((crate_id, req), version_id));
     let resolved = resolved.arrange_by_key();
 
-    // full dependency graph across all versions of all crates
-    type dependency_edges<'a> = stream![VersionId => VersionId; isize];
+    // full dependency graph across all versions of all crates, with which
+    // features of the dependency each edge enables
+    type dependency_edges<'a> =
+        stream![VersionId => (VersionId, DependencyKind, DefaultFeatures, Slice<FeatureId>); isize];
     let direct_dependency_edges: dependency_edges = dependencies
-        .map(|dep| ((dep.crate_id, dep.req), dep.version_id))
-        .KV::<(CrateId, VersionReq), VersionId>()
+        .map(|dep| {
+            (
+                (dep.crate_id, dep.req),
+                (dep.version_id, dep.kind, dep.default_features, dep.features),
+            )
+        })
+        .KV::<(CrateId, VersionReq), (VersionId, DependencyKind, DefaultFeatures, Slice<FeatureId>)>()
         .join_core(
             &resolved,
-            |(_crate_id, _req), from_version_id, to_version_id| {
-                once((*from_version_id, *to_version_id))
+            |(_crate_id, _req), (from_version_id, kind, default_features, features), to_version_id| {
+                once((
+                    *from_version_id,
+                    (*to_version_id, *kind, *default_features, *features),
+                ))
             },
         );
 
@@ -286,45 +427,16 @@ fn dataflow(
         .map(|(_crate_id, (_not_prerelease, _created_at, version_id))| version_id);
     let most_recent_crate_version = most_recent_crate_version.arrange_by_self();
 
-    // releases that satisfy the predicate of each query
-    type match_releases<'a> = stream![VersionId => QueryId; Present];
-    let match_releases: match_releases = queries
-        .flat_map(|query| {
-            query
-                .predicates
-                .iter()
-                .map(move |pred| (pred.crate_id, (query.id, pred.req)))
-        })
-        .KV::<CrateId, (QueryId, Option<VersionReq>)>()
-        .join_core(
-            &releases_by_crate_id,
-            |_crate_id, (query_id, version_req), (version_id, version)| {
-                let matches = match version_req {
-                    None => true,
-                    Some(req) => req.matches(version),
-                };
-                matches.then_some((*version_id, *query_id))
-            },
-        );
-
-    // releases that contribute into the result of each query
-    type query_results<'a> = stream![VersionId => QueryId; isize];
-    let mut query_results: query_results = direct_dependency_edges
-        .join_core(&most_recent_crate_version, |edge_from, edge_to, ()| {
-            once((*edge_to, *edge_from))
-        })
-        .KV::<VersionId, VersionId>()
-        .join_map(&match_releases, |_edge_to, edge_from, query_id| {
-            (*edge_from, *query_id)
-        });
-
-    if transitive {
+    // full dependency graph across all versions of all crates, from each feature
+    // of a release to the features it turns on of the same or another release,
+    // which is only needed in transitive mode
+    let transitive_dependency_edges = config.transitive.then(|| {
         type dependency_edges<'a> = stream![VersionFeature => VersionFeature; isize];
 
         // dependency edges arising from an entry under [dependencies]
         let dep_dependency_edges: dependency_edges = dependencies
-            .flat_map(|dep| match dep.kind {
-                DependencyKind::Normal | DependencyKind::Build => Some((
+            .flat_map(move |dep| {
+                config.transitive_kinds.contains(dep.kind).then_some((
                     (dep.crate_id, dep.req),
                     (
                         dep.version_id,
@@ -332,8 +444,7 @@ fn dataflow(
                         dep.default_features,
                         dep.features,
                     ),
-                )),
-                DependencyKind::Dev => None,
+                ))
             })
             .KV::<(CrateId, VersionReq), (VersionId, FeatureId, DefaultFeatures, Slice<FeatureId>)>(
             )
@@ -342,95 +453,38 @@ fn dataflow(
                 |(_crate_id, _req),
                  (version_id, feature_id, default_features, features),
                  resolved_version_id| {
-                    let edge_from = VersionFeature {
-                        version_id: *version_id,
-                        feature_id: *feature_id,
-                    };
-                    let resolved_version_id = *resolved_version_id;
-                    FeatureIter::new(*default_features, *features).map(move |feature_id| {
-                        let edge_to = VersionFeature {
-                            version_id: resolved_version_id,
-                            feature_id,
-                        };
-                        (edge_from, edge_to)
-                    })
+                    edges::dependency(
+                        *version_id,
+                        *feature_id,
+                        *default_features,
+                        *features,
+                        *resolved_version_id,
+                    )
                 },
             );
 
         // dependency edges from crate feature enabling other feature of same crate
         let feature_intracrate_edges: dependency_edges = releases.explode(|rel| {
-            let version_id = rel.id;
-            let crate_id = rel.crate_id;
-            rel.features
-                .iter()
-                .flat_map(move |feature| {
-                    let edge_from = VersionFeature {
-                        version_id,
-                        feature_id: feature.id,
-                    };
-                    feature
-                        .enables
-                        .into_iter()
-                        .filter_map(move |crate_feature| {
-                            if crate_feature.crate_id == crate_id {
-                                let edge_to = VersionFeature {
-                                    version_id,
-                                    feature_id: crate_feature.feature_id,
-                                };
-                                Some((edge_from, edge_to))
-                            } else {
-                                None
-                            }
-                        })
-                        .chain({
-                            if feature.id == FeatureId::DEFAULT {
-                                None
-                            } else {
-                                let edge_to = VersionFeature {
-                                    version_id,
-                                    feature_id: FeatureId::CRATE,
-                                };
-                                Some((edge_from, edge_to))
-                            }
-                        })
-                })
-                .chain({
-                    let edge_from = VersionFeature {
-                        version_id,
-                        feature_id: FeatureId::DEFAULT,
-                    };
-                    let edge_to = VersionFeature {
-                        version_id,
-                        feature_id: FeatureId::CRATE,
-                    };
-                    once((edge_from, edge_to))
-                })
-                .map(|(edge_from, edge_to)| ((edge_from, edge_to), 1))
+            edges::feature_intracrate(&rel).map(|(edge_from, edge_to)| ((edge_from, edge_to), 1))
         });
 
+        // crate feature weakly enabling feature of other crate, where the
+        // dependency on that crate is enabled regardless
+        let weak_feature_dependencies = releases
+            .flat_map(|rel| edges::weak_feature_dependency(&rel))
+            .KV::<(VersionId, CrateId, FeatureId), (FeatureId, FeatureId)>()
+            .join_map(
+                &dependencies
+                    .map(|dep| ((dep.version_id, dep.crate_id, dep.feature_id), dep.req))
+                    .KV::<(VersionId, CrateId, FeatureId), VersionReq>(),
+                |(version_id, crate_id, _dep_feature), (from_feature, to_feature), req| {
+                    ((*crate_id, *req), (*version_id, *from_feature, *to_feature))
+                },
+            );
+
         // dependency edges from crate feature enabling feature of other crate
         let feature_dependency_edges: dependency_edges = releases
-            .flat_map(|rel| {
-                let version_id = rel.id;
-                let crate_id = rel.crate_id;
-                rel.features.into_iter().flat_map(move |feature| {
-                    // TODO: also handle `weak_enables`
-                    // https://github.com/dtolnay/cargo-tally/issues/56
-                    feature
-                        .enables
-                        .into_iter()
-                        .filter_map(move |crate_feature| {
-                            if crate_feature.crate_id == crate_id {
-                                None
-                            } else {
-                                Some((
-                                    (version_id, crate_feature.crate_id),
-                                    (feature.id, crate_feature.feature_id),
-                                ))
-                            }
-                        })
-                })
-            })
+            .flat_map(|rel| edges::feature_dependency(&rel))
             .KV::<(VersionId, CrateId), (FeatureId, FeatureId)>()
             .join_map(
                 &dependencies
@@ -440,6 +494,7 @@ fn dataflow(
                     ((*crate_id, *req), (*version_id, *from_feature, *to_feature))
                 },
             )
+            .concat(&weak_feature_dependencies)
             .KV::<(CrateId, VersionReq), (VersionId, FeatureId, FeatureId)>()
             .join_core(
                 &resolved,
@@ -458,10 +513,272 @@ fn dataflow(
                 },
             );
 
-        // full dependency graph across all versions of all crates
-        let incoming_transitive_dependency_edges = dep_dependency_edges
+        // crate feature weakly enabling feature of other crate, where the
+        // dependency on that crate is enabled by other features turned on in
+        // the same declaration of a dependency, which makes for an edge right
+        // from the dependent
+        let declared_weak_feature_edges: dependency_edges = dependencies
+            .flat_map(move |dep| {
+                config.transitive_kinds.contains(dep.kind).then_some((
+                    (dep.crate_id, dep.req),
+                    (
+                        dep.version_id,
+                        dep.feature_id,
+                        dep.default_features,
+                        dep.features,
+                    ),
+                ))
+            })
+            .KV::<(CrateId, VersionReq), (VersionId, FeatureId, DefaultFeatures, Slice<FeatureId>)>(
+            )
+            .join_core(
+                &resolved,
+                |(_crate_id, _req),
+                 (version_id, feature_id, default_features, features),
+                 resolved_version_id| {
+                    once((
+                        *resolved_version_id,
+                        (*version_id, *feature_id, *default_features, *features),
+                    ))
+                },
+            )
+            .KV::<VersionId, (VersionId, FeatureId, DefaultFeatures, Slice<FeatureId>)>()
+            .join_core(
+                &releases_by_version_id,
+                |resolved_version_id, (version_id, feature_id, default_features, features), rel| {
+                    edges::weak_feature_dependency_declared(rel, *default_features, *features)
+                        .into_iter()
+                        .map(|((crate_id, dep_feature_id), to_feature_id)| {
+                            (
+                                (*resolved_version_id, crate_id, dep_feature_id),
+                                (*version_id, *feature_id, to_feature_id),
+                            )
+                        })
+                        .collect::<Vec<_>>()
+                },
+            )
+            .KV::<(VersionId, CrateId, FeatureId), (VersionId, FeatureId, FeatureId)>()
+            .join_map(
+                &dependencies
+                    .map(|dep| ((dep.version_id, dep.crate_id, dep.feature_id), dep.req))
+                    .KV::<(VersionId, CrateId, FeatureId), VersionReq>(),
+                |(_version_id, crate_id, _dep_feature),
+                 (from_version_id, from_feature, to_feature),
+                 req| {
+                    (
+                        (*crate_id, *req),
+                        (*from_version_id, *from_feature, *to_feature),
+                    )
+                },
+            )
+            .KV::<(CrateId, VersionReq), (VersionId, FeatureId, FeatureId)>()
+            .join_core(
+                &resolved,
+                |(_crate_id, _req),
+                 (from_version_id, from_feature_id, to_feature_id),
+                 to_version_id| {
+                    let edge_from = VersionFeature {
+                        version_id: *from_version_id,
+                        feature_id: *from_feature_id,
+                    };
+                    let edge_to = VersionFeature {
+                        version_id: *to_version_id,
+                        feature_id: *to_feature_id,
+                    };
+                    Some((edge_from, edge_to))
+                },
+            );
+
+        dep_dependency_edges
             .concat(&feature_intracrate_edges)
             .concat(&feature_dependency_edges)
+            .concat(&declared_weak_feature_edges)
+    });
+
+    if config.reverse {
+        // most recent release of every crate matching each query
+        type roots<'a> = stream![VersionId => QueryId; isize];
+        let roots: roots = queries
+            .flat_map(|query| {
+                query
+                    .terms
+                    .iter()
+                    .flatten()
+                    .map(move |pred| (pred.crate_id, query.id))
+            })
+            .KV::<CrateId, QueryId>()
+            .join_core(&releases_by_crate_id, |_crate_id, query_id, rel| {
+                Some((rel.id, *query_id))
+            })
+            .KV::<VersionId, QueryId>()
+            .join_core(&most_recent_crate_version, |version_id, query_id, ()| {
+                Some((*version_id, *query_id))
+            })
+            .KV::<VersionId, QueryId>()
+            .distinct();
+
+        let dependency_results: roots = match transitive_dependency_edges {
+            // releases that those depend on directly
+            None => roots
+                .join_map(
+                    &direct_dependency_edges,
+                    |_version_id, query_id, (to_version_id, kind, _default_features, _features)| {
+                        (*query_id, *to_version_id, *kind)
+                    },
+                )
+                .flat_map(move |(query_id, version_id, kind)| {
+                    config
+                        .direct_kinds
+                        .contains(kind)
+                        .then_some((version_id, query_id))
+                }),
+
+            // releases that those depend on with their default features, at any
+            // distance, following the same kinds of dependency all the way
+            Some(transitive_dependency_edges) => {
+                // the roots along with which root each one is, so that other
+                // crates matching the same query can still be counted as a
+                // dependency of this one
+                type root_features<'a> = stream![VersionFeature => (VersionId, QueryId); isize];
+                let root_features: root_features = roots.flat_map(|(version_id, query_id)| {
+                    [FeatureId::CRATE, FeatureId::DEFAULT].map(|feature_id| {
+                        let version_feature = VersionFeature {
+                            version_id,
+                            feature_id,
+                        };
+                        (version_feature, (version_id, query_id))
+                    })
+                });
+
+                let outgoing_transitive_dependency_edges = transitive_dependency_edges
+                    .KV::<VersionFeature, VersionFeature>()
+                    .arrange_by_key();
+
+                // fixed point of transitive dependencies graph, going forward
+                scope
+                    .iterative::<u16, _, _>(|nested| {
+                        let root_features = root_features
+                            .KV::<VersionFeature, (VersionId, QueryId)>()
+                            .enter(nested);
+                        let summary = Product::new(Duration::default(), 1);
+                        let variable = Variable::new_from(root_features, summary);
+                        let result = variable
+                            .deref()
+                            .KV::<VersionFeature, (VersionId, QueryId)>()
+                            .join_core(
+                                &outgoing_transitive_dependency_edges.enter(nested),
+                                |_edge_from, root, edge_to| Some((*edge_to, *root)),
+                            )
+                            .KV::<VersionFeature, (VersionId, QueryId)>()
+                            .concat(&variable)
+                            .KV::<VersionFeature, (VersionId, QueryId)>()
+                            .distinct();
+                        variable.set(&result).leave()
+                    })
+                    .KV::<VersionFeature, (VersionId, QueryId)>()
+                    .flat_map(|(version_feature, (root, query_id))| {
+                        // not counting the root itself
+                        (version_feature.version_id != root)
+                            .then_some((version_feature.version_id, query_id))
+                    })
+            }
+        };
+
+        // one release of each crate depended on, the greatest version, even
+        // if different dependents resolve it to different releases
+        return dependency_results
+            .distinct()
+            .join_core(&releases_by_version_id, |version_id, query_id, rel| {
+                Some(((rel.crate_id, *query_id), (rel.num.clone(), *version_id)))
+            })
+            .KV::<(CrateId, QueryId), (Version, VersionId)>()
+            .reduce(|(_crate_id, _query_id), input, output| {
+                let ((_version, version_id), _diff) = input.last().unwrap();
+                output.push((*version_id, 1));
+            })
+            .map(|((_crate_id, query_id), version_id)| (version_id, query_id));
+    }
+
+    // releases that satisfy the predicate of each query, which dependencies on
+    // them count toward it by kind and by default features, and for a
+    // predicate on a feature, which features of the release turn that one on
+    type match_releases<'a> = stream![VersionId => (
+        (QueryId, TermId),
+        Option<DependencyKinds>,
+        Option<DefaultFeatures>,
+        Option<Slice<FeatureId>>,
+    ); Present];
+    let match_releases: match_releases = queries
+        .flat_map(|query| {
+            query
+                .terms
+                .iter()
+                .zip(0..)
+                .flat_map(move |(predicates, i)| {
+                    let term = (query.id, TermId(i));
+                    predicates.iter().map(move |pred| {
+                        (
+                            pred.crate_id,
+                            (
+                                term,
+                                pred.req,
+                                pred.kinds,
+                                pred.default_features,
+                                pred.feature,
+                            ),
+                        )
+                    })
+                })
+        })
+        .KV::<CrateId, (
+            (QueryId, TermId),
+            Option<VersionReq>,
+            Option<DependencyKinds>,
+            Option<DefaultFeatures>,
+            Option<FeatureId>,
+        )>()
+        .join_core(
+            &releases_by_crate_id,
+            |_crate_id, (term, version_req, kinds, default_features, feature), rel| {
+                let matches = match version_req {
+                    None => true,
+                    Some(req) => req.matches(&rel.num),
+                };
+                let enabling = feature
+                    .map(|feature_id| Slice::new(&edges::enabling_features(rel, feature_id)));
+                matches.then_some((rel.id, (*term, *kinds, *default_features, enabling)))
+            },
+        );
+    let match_releases_by_version_id = match_releases.arrange_by_key();
+
+    // releases that contribute into the result of each term of each query
+    type term_results<'a> = stream![VersionId => (QueryId, TermId); isize];
+    let mut term_results: term_results = direct_dependency_edges
+        .join_core(
+            &most_recent_crate_version,
+            |edge_from, (edge_to, kind, default_features, features), ()| {
+                once((*edge_to, (*edge_from, *kind, *default_features, *features)))
+            },
+        )
+        .KV::<VersionId, (VersionId, DependencyKind, DefaultFeatures, Slice<FeatureId>)>()
+        .join_core(
+            &match_releases_by_version_id,
+            move |_edge_to,
+                  (edge_from, kind, default_features, features),
+                  (term, kinds, keeps_default_features, enabling)| {
+                let counts = kinds.unwrap_or(config.direct_kinds).contains(*kind)
+                    && keeps_default_features.is_none_or(|keeps| keeps == *default_features)
+                    && enabling.is_none_or(|enabling| {
+                        edges::enables_any(*default_features, *features, enabling)
+                    });
+                counts.then_some((*edge_from, *term))
+            },
+        );
+
+    if let Some(transitive_dependency_edges) = transitive_dependency_edges {
+        // the same graph with every edge pointing backward, from the dependency to
+        // the dependent
+        let incoming_transitive_dependency_edges = transitive_dependency_edges
             .KV::<VersionFeature, VersionFeature>()
             .map_in_place(|edge| {
                 let (edge_from, edge_to) = *edge;
@@ -470,50 +787,181 @@ fn dataflow(
             .KV::<VersionFeature, VersionFeature>()
             .arrange_by_key();
 
+        // where the search for transitive dependents starts from: every release
+        // satisfying a predicate that does not restrict the dependency on it (or
+        // the features of it that turn on the predicate's feature), and for
+        // predicates that do, the feature of every release that enables a
+        // dependency of that sort on a satisfying release, which is one hop
+        // along already
+        type match_features<'a> = stream![VersionFeature => ((QueryId, TermId), u16); isize];
+        let match_features: match_features = match_releases
+            .explode(|(version_id, (term, kinds, default_features, enabling))| {
+                let restricted = kinds.is_some() || default_features.is_some();
+                let features = match enabling {
+                    _ if restricted => Vec::new(),
+                    None => vec![FeatureId::CRATE],
+                    Some(enabling) => enabling.iter().collect(),
+                };
+                features.into_iter().map(move |feature_id| {
+                    let version_feature = VersionFeature {
+                        version_id,
+                        feature_id,
+                    };
+                    ((version_feature, (term, 0)), 1)
+                })
+            })
+            .KV::<VersionFeature, ((QueryId, TermId), u16)>()
+            .concat(
+                &dependencies
+                    .map(|dep| {
+                        let edge_from = VersionFeature {
+                            version_id: dep.version_id,
+                            feature_id: dep.feature_id,
+                        };
+                        (
+                            (dep.crate_id, dep.req),
+                            (edge_from, dep.kind, dep.default_features, dep.features),
+                        )
+                    })
+                    .KV::<(CrateId, VersionReq), (
+                        VersionFeature,
+                        DependencyKind,
+                        DefaultFeatures,
+                        Slice<FeatureId>,
+                    )>()
+                    .join_core(
+                        &resolved,
+                        |(_crate_id, _req),
+                         (edge_from, kind, default_features, features),
+                         to_version_id| {
+                            once((
+                                *to_version_id,
+                                (*edge_from, *kind, *default_features, *features),
+                            ))
+                        },
+                    )
+                    .KV::<VersionId, (
+                        VersionFeature,
+                        DependencyKind,
+                        DefaultFeatures,
+                        Slice<FeatureId>,
+                    )>()
+                    .join_core(
+                        &match_releases_by_version_id,
+                        move |_edge_to,
+                         (edge_from, kind, default_features, features),
+                         (term, kinds, keeps_default_features, enabling)| {
+                            let restricted = kinds.is_some() || keeps_default_features.is_some();
+                            let counts = restricted
+                                && kinds.unwrap_or(config.transitive_kinds).contains(*kind)
+                                && keeps_default_features
+                                    .is_none_or(|keeps| keeps == *default_features)
+                                && enabling.is_none_or(|enabling| {
+                                    edges::enables_any(*default_features, *features, enabling)
+                                });
+                            counts.then_some((*edge_from, (*term, 1)))
+                        },
+                    ),
+            );
+
+        // how many more dependencies the search may cross from each starting
+        // point before it stops counting dependents of the query
+        type query_depths<'a> = stream![QueryId => Option<u16>; Present];
+        let query_depths: query_depths = queries.map(|query| (query.id, query.depth));
+        type remaining_features<'a> =
+            stream![VersionFeature => ((QueryId, TermId), Option<u16>); isize];
+        let remaining_features: remaining_features = match_features
+            .map(|(version_feature, (term, hops))| (term.0, (version_feature, term, hops)))
+            .KV::<QueryId, (VersionFeature, (QueryId, TermId), u16)>()
+            .join_core(
+                &query_depths.arrange_by_key(),
+                |_query_id, (version_feature, term, hops), depth| {
+                    let remaining = depth.map(|depth| depth.checked_sub(*hops));
+                    Some((*version_feature, (*term, remaining)))
+                },
+            )
+            .flat_map(|(version_feature, (term, remaining))| match remaining {
+                None => Some((version_feature, (term, None))),
+                Some(None) => None,
+                Some(Some(remaining)) => Some((version_feature, (term, Some(remaining)))),
+            });
+
         // fixed point of transitive dependencies graph
-        type addend_transitive_releases<'a> = stream![VersionId => QueryId; isize];
+        type addend_transitive_releases<'a> = stream![VersionId => (QueryId, TermId); isize];
         let addend_transitive_releases: addend_transitive_releases = scope
             .iterative::<u16, _, _>(|nested| {
-                let match_releases = match_releases
-                    .KV::<VersionId, QueryId>()
-                    .explode(|(version_id, query_id)| {
-                        let version_feature = VersionFeature {
-                            version_id,
-                            feature_id: FeatureId::CRATE,
-                        };
-                        once(((version_feature, query_id), 1))
-                    })
-                    .KV::<VersionFeature, QueryId>()
+                let remaining_features = remaining_features
+                    .KV::<VersionFeature, ((QueryId, TermId), Option<u16>)>()
                     .enter(nested);
                 let summary = Product::new(Duration::default(), 1);
-                let variable = Variable::new_from(match_releases, summary);
+                let variable = Variable::new_from(remaining_features, summary);
                 let result = variable
                     .deref()
-                    .KV::<VersionFeature, QueryId>()
+                    .KV::<VersionFeature, ((QueryId, TermId), Option<u16>)>()
                     .join_core(
                         &incoming_transitive_dependency_edges.enter(nested),
-                        |_edge_to, query_id, edge_from| Some((*edge_from, *query_id)),
+                        |edge_to, (term, remaining), edge_from| {
+                            // only an edge onto a different release is a hop;
+                            // features within a release are free to follow
+                            let remaining = match remaining {
+                                Some(remaining) if edge_from.version_id != edge_to.version_id => {
+                                    Some(remaining.checked_sub(1)?)
+                                }
+                                remaining => *remaining,
+                            };
+                            Some((*edge_from, (*term, remaining)))
+                        },
                     )
-                    .KV::<VersionFeature, QueryId>()
+                    .KV::<VersionFeature, ((QueryId, TermId), Option<u16>)>()
                     .concat(&variable)
-                    .KV::<VersionFeature, QueryId>()
+                    .KV::<VersionFeature, ((QueryId, TermId), Option<u16>)>()
                     .distinct();
                 variable.set(&result).leave()
             })
-            .KV::<VersionFeature, QueryId>()
-            .map(|(version_feature, query_id)| (version_feature.version_id, query_id));
+            .KV::<VersionFeature, ((QueryId, TermId), Option<u16>)>()
+            .map(|(version_feature, (term, _remaining))| (version_feature.version_id, term));
 
-        query_results = addend_transitive_releases
-            .join_core(&most_recent_crate_version, |version_id, query_id, ()| {
-                Some((*version_id, *query_id))
+        term_results = addend_transitive_releases
+            .join_core(&most_recent_crate_version, |version_id, term, ()| {
+                Some((*version_id, *term))
             })
-            .KV::<VersionId, QueryId>()
-            .concat(&query_results);
+            .KV::<VersionId, (QueryId, TermId)>()
+            .concat(&term_results);
     }
 
-    query_results
+    // releases that satisfy none of the terms of a query which counts those
+    // anyway, like `!serde`
+    type unsatisfied<'a> = stream![VersionId => QueryId; isize];
+    let unsatisfied: unsatisfied = queries
+        .filter(|query| eval(query.expr, |_term_id| false))
+        .map(|query| ((), query.id))
+        .KV::<(), QueryId>()
+        .join_map(
+            &most_recent_crate_version
+                .as_collection(|version_id, ()| ((), *version_id))
+                .KV::<(), VersionId>(),
+            |(), query_id, version_id| (*version_id, *query_id),
+        );
+
+    // releases that contribute into the result of each query, according to
+    // which of its terms they satisfy
+    type query_exprs<'a> = stream![QueryId => Slice<Op>; Present];
+    let query_exprs: query_exprs = queries.map(|query| (query.id, query.expr));
+    let query_exprs = query_exprs.arrange_by_key();
+    term_results
         .distinct()
-        .map(|(_version_id, query_id)| query_id)
-        .consolidate()
-        .collect_into(results);
+        .map(|(version_id, (query_id, term_id))| (query_id, (version_id, Some(term_id))))
+        .concat(&unsatisfied.map(|(version_id, query_id)| (query_id, (version_id, None))))
+        .KV::<QueryId, (VersionId, Option<TermId>)>()
+        .join_core(&query_exprs, |query_id, (version_id, term_id), expr| {
+            once(((*version_id, *query_id, *expr), *term_id))
+        })
+        .KV::<(VersionId, QueryId, Slice<Op>), Option<TermId>>()
+        .reduce(|(_version_id, _query_id, expr), input, output| {
+            let satisfied = |term_id| input.iter().any(|(term, _diff)| **term == Some(term_id));
+            if eval(*expr, satisfied) {
+                output.push(((), 1));
+            }
+        })
+        .map(|((version_id, query_id, _expr), ())| (version_id, query_id))
 }
@@ -10,7 +10,9 @@ use cargo_tally::id::{CrateId, DependencyId, VersionId};
 use cargo_tally::timestamp::DateTime;
 use cargo_tally::version::{Version, VersionReq};
 use cargo_tally::{DbDump, Dependency, Release};
+use db_dump::categories::CategoryId;
 use db_dump::crate_owners::OwnerId;
+use db_dump::keywords::KeywordId;
 use std::cell::RefCell;
 use std::collections::{BTreeMap as Map, BTreeSet as Set};
 use std::mem;
@@ -21,6 +23,10 @@ pub(crate) fn load(path: impl AsRef<Path>) -> Result<(DbDump, CrateMap)> {
     let mut users: Map<User, OwnerId> = Map::new();
     let mut teams: Map<User, OwnerId> = Map::new();
     let mut owners: Map<OwnerId, Vec<CrateId>> = Map::new();
+    let mut categories: Map<CategoryId, String> = Map::new();
+    let mut crates_categories: Vec<(CategoryId, CrateId)> = Vec::new();
+    let mut keywords: Map<KeywordId, String> = Map::new();
+    let mut crates_keywords: Vec<(KeywordId, CrateId)> = Vec::new();
     let mut releases: Vec<Release> = Vec::new();
     let mut dependencies: Vec<Dependency> = Vec::new();
     let mut release_features: Vec<Vec<(FeatureId, Vec<CrateFeature>, Vec<CrateFeature>)>> =
@@ -45,6 +51,18 @@ pub(crate) fn load(path: impl AsRef<Path>) -> Result<(DbDump, CrateMap)> {
                 }
             }
         })
+        .categories(|row| {
+            categories.insert(row.id, row.slug);
+        })
+        .crates_categories(|row| {
+            crates_categories.push((row.category_id, CrateId::from(row.crate_id)));
+        })
+        .keywords(|row| {
+            keywords.insert(row.id, row.keyword);
+        })
+        .crates_keywords(|row| {
+            crates_keywords.push((row.keyword_id, CrateId::from(row.crate_id)));
+        })
         .crate_owners(|row| {
             owners
                 .entry(row.owner_id)
@@ -204,6 +222,24 @@ pub(crate) fn load(path: impl AsRef<Path>) -> Result<(DbDump, CrateMap)> {
     crates.owners = owners;
     crates.users = users;
     crates.users.extend(teams);
+    for (category_id, crate_id) in crates_categories {
+        if let Some(slug) = categories.get(&category_id) {
+            crates
+                .categories
+                .entry(slug.clone())
+                .or_insert_with(Vec::new)
+                .push(crate_id);
+        }
+    }
+    for (keyword_id, crate_id) in crates_keywords {
+        if let Some(keyword) = keywords.get(&keyword_id) {
+            crates
+                .keywords
+                .entry(keyword.clone())
+                .or_insert_with(Vec::new)
+                .push(crate_id);
+        }
+    }
 
     crate::mend::mend_releases(&mut db_dump, &crates);
 
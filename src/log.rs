@@ -7,8 +7,13 @@ pub trait Log {
     fn warning(&mut self) -> LogStream;
     fn error(&mut self) -> LogStream;
     fn red(&mut self) -> LogStream;
+    fn series(&mut self, i: usize) -> LogStream;
 }
 
+// Closest 256-color terminal equivalents of d3.schemeCategory10, the colors of
+// the lines in the HTML graph.
+const SERIES: [u8; 10] = [32, 208, 34, 160, 97, 94, 175, 244, 142, 37];
+
 impl Log for StandardStream {
     fn trace(&mut self) -> LogStream {
         let mut color = ColorSpec::new();
@@ -40,6 +45,13 @@ impl Log for StandardStream {
         let _ = self.set_color(&color);
         LogStream(self)
     }
+
+    fn series(&mut self, i: usize) -> LogStream {
+        let mut color = ColorSpec::new();
+        color.set_fg(Some(Color::Ansi256(SERIES[i % SERIES.len()])));
+        let _ = self.set_color(&color);
+        LogStream(self)
+    }
 }
 
 pub struct LogStream<'a>(&'a mut StandardStream);
@@ -14,6 +14,7 @@
     clippy::needless_lifetimes,
     clippy::redundant_else,
     clippy::single_match_else,
+    clippy::struct_excessive_bools,
     clippy::too_many_lines,
     clippy::type_complexity,
     clippy::unconditional_recursion, // https://github.com/rust-lang/rust-clippy/issues/12133
@@ -28,24 +29,43 @@ mod macros;
 
 mod alloc;
 mod args;
+mod chain;
 mod clean;
+#[cfg(test)]
+#[path = "../tests/common/mod.rs"]
+mod common;
 mod cratemap;
 mod cratename;
+mod dependents;
 mod filter;
+mod format;
 mod load;
 mod log;
 mod mend;
 mod query;
 mod render;
+mod svg;
+mod syntax;
+mod term;
+mod top;
 mod total;
 mod trace;
 mod user;
 
+use crate::format::Format;
 use crate::load::load;
 use crate::log::Log;
+use crate::render::Plot;
 use crate::total::Total;
-use anyhow::Result;
-use std::io::{self, IsTerminal, Write};
+use anyhow::{bail, Result};
+use cargo_tally::dependency::{DependencyKinds, Optional};
+use cargo_tally::timestamp::DateTime;
+use cargo_tally::Config;
+use std::cmp;
+use std::fs::File;
+use std::io::{self, BufWriter, IsTerminal, Write};
+use std::iter;
+use std::path::Path;
 use std::process;
 use std::time::Instant;
 use termcolor::{ColorChoice, StandardStream};
@@ -78,7 +98,11 @@ fn try_main(stderr: &mut StandardStream) -> Result<()> {
     let mut sysinfo = sysinfo::System::new();
     sysinfo.refresh_memory();
     let total_memory = sysinfo.total_memory();
-    let (min_memory, advised) = if opt.transitive {
+    let (min_memory, advised) = if opt.transitive && opt.optional == Optional::Only {
+        // The transitive tally runs twice, with and without optional
+        // dependencies, to take the difference.
+        (20 * 1024 * 1024 * 1024, "24 GB")
+    } else if opt.transitive {
         (10 * 1024 * 1024 * 1024, "12 GB")
     } else {
         (7 * 1024 * 1024 * 1024, "8 GB")
@@ -103,10 +127,138 @@ fn try_main(stderr: &mut StandardStream) -> Result<()> {
         writeln!(stderr.trace(), "load time: {:.2?}", instant.elapsed());
     }
 
-    let query_strings = opt.queries.iter().map(String::as_str);
-    let queries = query::parse(query_strings, &crates)?;
+    let query_strings = if opt.by_major {
+        query::split_major(&opt.queries, &crates, &db_dump.releases)?
+    } else if opt.by_version {
+        query::split_resolved(&opt.queries, &crates, &db_dump.releases)?
+    } else {
+        opt.queries.clone()
+    };
+    let mut queries = query::parse(
+        query_strings.iter().map(String::as_str),
+        &crates,
+        &db_dump.features,
+    )?;
+    let mut labels = query_strings
+        .iter()
+        .map(|query| query::format(query, &crates))
+        .collect::<Vec<_>>();
+    if opt.dependencies_of {
+        let prefix = if opt.transitive {
+            "transitive dependencies of"
+        } else {
+            "dependencies of"
+        };
+        for label in &mut labels {
+            *label = format!("{} {}", prefix, label);
+        }
+    }
+    let mut term_labels = query_strings
+        .iter()
+        .map(|query| query::format_terms(query, &crates))
+        .collect::<Vec<_>>();
+    if opt.by_default_features {
+        (queries, labels) = query::split_default_features(&queries, &labels)?;
+        term_labels = term_labels
+            .into_iter()
+            .flat_map(|mut terms| {
+                terms.push("no default features".to_owned());
+                [terms.clone(), terms]
+            })
+            .collect();
+    }
+    if !opt.depth.is_empty() {
+        (queries, labels) = query::split_depth(&queries, &labels, &opt.depth)?;
+        term_labels = term_labels
+            .into_iter()
+            .flat_map(|terms| iter::repeat_n(terms, opt.depth.len()))
+            .collect();
+    }
+
+    // By default every kind of dependency counts toward a query, but only what
+    // gets built along with the dependent is followed transitively.
+    let config = Config {
+        transitive: opt.transitive,
+        direct_kinds: opt.kind.unwrap_or(DependencyKinds::ALL),
+        transitive_kinds: opt.kind.unwrap_or(DependencyKinds::BUILD),
+        optional: opt.optional,
+        reverse: opt.dependencies_of,
+    };
+
+    if opt.top.is_some() || opt.trending {
+        let (queries, crate_ids) = top::queries(&crates)?;
+        let at = opt.at.unwrap_or_else(DateTime::now);
+        let since = opt.change.map(|months| at.months_before(months));
+        let instant = Instant::now();
+        let results = cargo_tally::run(db_dump, opt.jobs, config, &queries);
+        if stderr_isatty {
+            writeln!(stderr.trace(), "dataflow time: {:.2?}", instant.elapsed());
+        }
+        let _ = stderr.flush();
+        let stdout = io::stdout();
+        let mut stdout = stdout.lock();
+        let _ = if opt.trending {
+            // Over the last year unless told otherwise, 20 crates to a list.
+            let top = opt.top.unwrap_or(20);
+            let since = since.unwrap_or_else(|| at.months_before(12));
+            top::print_trending(&mut stdout, &results, &crate_ids, &crates, top, at, since)
+        } else {
+            let top = opt.top.unwrap();
+            top::print(&mut stdout, &results, &crate_ids, &crates, top, at, since)
+        };
+        let _ = stdout.flush();
+        return Ok(());
+    }
+
+    if let Some(dependent) = &opt.explain {
+        let Some(crate_id) = crates.id(dependent) else {
+            bail!("no crate named {}", dependent);
+        };
+        let _ = stderr.flush();
+        let stdout = io::stdout();
+        let mut stdout = stdout.lock();
+        let _ = chain::print(
+            &mut stdout,
+            &db_dump,
+            config,
+            &queries,
+            crate_id,
+            &crates,
+            &labels,
+            &term_labels,
+        );
+        let _ = stdout.flush();
+        return Ok(());
+    }
+
+    if opt.at.is_some() || opt.diff.is_some() {
+        let releases = dependents::Releases::index(&db_dump.releases);
+        let until = opt.at.or(opt.diff.map(|(_since, until)| until)).unwrap();
+        let instant = Instant::now();
+        let changes = cargo_tally::dependents(db_dump, opt.jobs, config, &queries, until);
+        if stderr_isatty {
+            writeln!(stderr.trace(), "dataflow time: {:.2?}", instant.elapsed());
+        }
+        let _ = stderr.flush();
+        let stdout = io::stdout();
+        let mut stdout = stdout.lock();
+        let _ = if let Some((since, _until)) = opt.diff {
+            dependents::print_diff(&mut stdout, &changes, since, &releases, &crates, &labels)
+        } else {
+            dependents::print_at(&mut stdout, &changes, &releases, &crates, &labels)
+        };
+        let _ = stdout.flush();
+        return Ok(());
+    }
+
     let instant = Instant::now();
-    let results = cargo_tally::run(db_dump, opt.jobs, opt.transitive, &queries);
+    let mut results = cargo_tally::run(db_dump, opt.jobs, config, &queries);
+    results.clip(opt.since, opt.until);
+    if let Some(interval) = opt.interval {
+        let now = DateTime::now();
+        let end = opt.until.map_or(now, |until| cmp::min(until, now));
+        results.resample(interval, end);
+    }
     if stderr_isatty {
         writeln!(stderr.trace(), "dataflow time: {:.2?}", instant.elapsed());
     }
@@ -115,39 +267,78 @@ fn try_main(stderr: &mut StandardStream) -> Result<()> {
     let len = results.len();
     let stdout = io::stdout();
     let mut stdout = stdout.lock();
-    for (i, (timestamp, data)) in results.iter().enumerate() {
-        if stdout_isatty && 10 + i == len && len > 20 {
-            let _ = writeln!(stdout, "...");
-        }
-        if !stdout_isatty || i < 10 || 10 + i >= len {
-            if let Some(total) = &total {
-                let total = total.eval(timestamp);
-                let _ = writeln!(stdout, "{:?} {:?}", timestamp, data / total);
+    let svg = matches!(opt.format, Some(Format::Svg));
+    // With a --format other than svg, --output is where the rows go instead of
+    // stdout, and there is no graph.
+    let (output, data_output) = if svg && opt.output.is_none() {
+        (Some(Path::new("-")), None)
+    } else if opt.format.is_some() && !svg {
+        (None, opt.output.as_deref())
+    } else {
+        (opt.output.as_deref(), None)
+    };
+    let graph_to_stdout = output == Some(Path::new("-"));
+    if graph_to_stdout {
+        // Stdout is reserved for the graph.
+    } else if svg {
+        // The graph is the only output.
+    } else if opt.plot == Plot::Term {
+        if results.is_empty() {
+            writeln!(stderr.red(), "zero results");
+        } else {
+            let color = if stdout_isatty {
+                ColorChoice::Auto
             } else {
-                let _ = writeln!(stdout, "{:?} {:?}", timestamp, data);
+                ColorChoice::Never
+            };
+            term::draw(
+                &mut StandardStream::stdout(color),
+                &opt,
+                &results,
+                &labels,
+                total.as_ref(),
+            );
+        }
+    } else if let Some(format) = opt.format {
+        match data_output {
+            Some(path) if path != Path::new("-") => {
+                let mut file = BufWriter::new(File::create(path)?);
+                format::write(&mut file, format, &results, &labels, total.as_ref())?;
+                file.flush()?;
+                writeln!(stderr.trace(), "rows written to {}", path.display());
+            }
+            _ => {
+                let _ = format::write(&mut stdout, format, &results, &labels, total.as_ref());
+            }
+        }
+    } else {
+        for (i, (timestamp, data)) in results.iter().enumerate() {
+            if stdout_isatty && 10 + i == len && len > 20 {
+                let _ = writeln!(stdout, "...");
+            }
+            if !stdout_isatty || i < 10 || 10 + i >= len {
+                if let Some(total) = &total {
+                    let total = total.eval(timestamp);
+                    let _ = writeln!(stdout, "{:?} {:?}", timestamp, data / total);
+                } else {
+                    let _ = writeln!(stdout, "{:?} {:?}", timestamp, data);
+                }
             }
         }
     }
     let _ = stdout.flush();
 
-    let graph_path = if stdout_isatty {
+    let open_graph = stdout_isatty && opt.format.is_none() && opt.plot == Plot::Html;
+    let graph_path = if output.is_some() || open_graph {
         if results.is_empty() {
             writeln!(stderr.red(), "zero results");
             None
+        } else if svg {
+            let svg = svg::graph(&opt, &results, &labels, total.as_ref());
+            render::write(&svg, output)?
         } else {
-            let labels = opt
-                .queries
-                .iter()
-                .map(|query| query::format(query, &crates))
-                .collect::<Vec<_>>();
-            let graph_path = render::graph(
-                opt.title.as_deref(),
-                opt.transitive,
-                &results,
-                &labels,
-                total.as_ref(),
-            )?;
-            Some(graph_path)
+            let html = render::graph(&opt, &results, &labels, total.as_ref())?;
+            render::write(&html, output)?
         }
     } else {
         None
@@ -159,7 +350,9 @@ fn try_main(stderr: &mut StandardStream) -> Result<()> {
 
     if let Some(path) = graph_path {
         writeln!(stderr.trace(), "graph written to {}", path.display());
-        let _ = opener::open(&path);
+        if !opt.no_open {
+            let _ = opener::open(&path);
+        }
     }
 
     Ok(())
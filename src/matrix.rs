@@ -1,50 +1,151 @@
-use crate::timestamp::DateTime;
-use ref_cast::RefCast;
+use crate::timestamp::{DateTime, Interval};
+use std::cmp;
 use std::fmt::{self, Debug};
-use std::iter::Copied;
-use std::ops::{Deref, Div, Index};
-use std::slice;
+use std::mem;
+use std::ops::{Div, Index, Range};
 
+// Values of every query over time. Most queries change at only a small fraction
+// of the timestamps, so rather than a full row per timestamp, each query keeps
+// just the rows at which its value changed.
 pub struct Matrix {
-    queries: usize,
-    rows: Vec<(DateTime, Vec<u32>)>,
+    timestamps: Vec<DateTime>,
+    // Per query: index of a row at which the value changed, and the value from
+    // that row on. Before the first change, the value is 0.
+    columns: Vec<Vec<(u32, u32)>>,
 }
 
-#[derive(RefCast)]
-#[repr(transparent)]
-pub struct Row([u32]);
+#[derive(Copy, Clone)]
+pub struct Row<'a> {
+    matrix: &'a Matrix,
+    index: usize,
+}
 
 impl Matrix {
-    pub(crate) fn new(queries: usize) -> Self {
+    pub fn new(queries: usize) -> Self {
         Matrix {
-            queries,
-            rows: Vec::new(),
+            timestamps: Vec::new(),
+            columns: vec![Vec::new(); queries],
         }
     }
 
     pub fn width(&self) -> usize {
-        self.queries
+        self.columns.len()
     }
 
     pub fn is_empty(&self) -> bool {
-        self.rows.is_empty()
+        self.timestamps.is_empty()
     }
 
     pub fn len(&self) -> usize {
-        self.rows.len()
+        self.timestamps.len()
     }
 
     pub fn iter(&self) -> Iter {
-        Iter(self.rows.iter())
+        Iter {
+            matrix: self,
+            range: 0..self.timestamps.len(),
+        }
     }
 
-    pub(crate) fn push(&mut self, timestamp: DateTime, data: Vec<u32>) {
-        self.rows.push((timestamp, data));
+    // Timestamp and new value of each change in the value of one query.
+    pub fn column(&self, i: usize) -> impl Iterator<Item = (DateTime, u32)> + '_ {
+        self.columns[i]
+            .iter()
+            .map(|&(row, value)| (self.timestamps[row as usize], value))
+    }
+
+    // Appends a row in which the given queries take on new values and the rest
+    // keep their value from the previous row.
+    pub fn push(&mut self, timestamp: DateTime, changes: impl IntoIterator<Item = (usize, u32)>) {
+        let row = u32::try_from(self.timestamps.len()).unwrap();
+        self.timestamps.push(timestamp);
+        for (i, value) in changes {
+            let column = &mut self.columns[i];
+            if column.last().map_or(0, |&(_row, value)| value) != value {
+                column.push((row, value));
+            }
+        }
+    }
+
+    // Restricts the rows to the half-open window `since..until`. The values
+    // in effect at `since` are kept as a row at `since`.
+    pub fn clip(&mut self, since: Option<DateTime>, until: Option<DateTime>) {
+        let mut start = 0;
+        let mut end = self.timestamps.len();
+        if let Some(until) = until {
+            end = self
+                .timestamps
+                .partition_point(|timestamp| *timestamp < until);
+        }
+        let mut rows: Vec<(DateTime, usize)> = Vec::new();
+        if let Some(since) = since {
+            start = self.timestamps[..end].partition_point(|timestamp| *timestamp <= since);
+            if start > 0 {
+                rows.push((since, start - 1));
+            }
+        }
+        rows.extend((start..end).map(|row| (self.timestamps[row], row)));
+        self.select(rows);
+    }
+
+    // Replaces the rows with one row per period, from the end of the first
+    // row's period through the end of the window at `end`. Each is timestamped
+    // with the instant the period ends and holds the values in effect at that
+    // instant, so the values carry forward through periods in which nothing
+    // changed. A period still in progress at `end` gets a final row at `end`.
+    pub fn resample(&mut self, interval: Interval, end: DateTime) {
+        let mut rows = Vec::new();
+        if let Some(&first) = self.timestamps.first().filter(|&&first| first <= end) {
+            let mut boundary = if first.start_of_period(interval) == first {
+                first
+            } else {
+                first.end_of_period(interval)
+            };
+            loop {
+                let at = cmp::min(boundary, end);
+                let row = self
+                    .timestamps
+                    .partition_point(|timestamp| *timestamp <= at);
+                rows.push((at, row - 1));
+                if boundary >= end {
+                    break;
+                }
+                boundary = boundary.end_of_period(interval);
+            }
+        }
+        self.select(rows);
+    }
+
+    // Rebuilds the matrix out of the given timestamps, each holding the values
+    // of an existing row. Rows must be given in nondecreasing order.
+    fn select(&mut self, rows: Vec<(DateTime, usize)>) {
+        let sources: Vec<usize> = rows.iter().map(|&(_timestamp, row)| row).collect();
+        for column in &mut self.columns {
+            let old = mem::take(column);
+            for (k, &(row, value)) in old.iter().enumerate() {
+                // The first new row that takes its values from this old row or
+                // later, as long as that is before the next change.
+                let first = sources.partition_point(|&source| source < row as usize);
+                let in_effect = match old.get(k + 1) {
+                    Some(&(next, _value)) => sources
+                        .get(first)
+                        .is_some_and(|&source| source < next as usize),
+                    None => first < sources.len(),
+                };
+                if in_effect && column.last().map_or(0, |&(_row, value)| value) != value {
+                    column.push((u32::try_from(first).unwrap(), value));
+                }
+            }
+        }
+        self.timestamps = rows
+            .into_iter()
+            .map(|(timestamp, _row)| timestamp)
+            .collect();
     }
 }
 
 impl<'a> IntoIterator for &'a Matrix {
-    type Item = (DateTime, &'a Row);
+    type Item = (DateTime, Row<'a>);
     type IntoIter = Iter<'a>;
 
     fn into_iter(self) -> Self::IntoIter {
@@ -52,57 +153,90 @@ impl<'a> IntoIterator for &'a Matrix {
     }
 }
 
-pub struct Iter<'a>(slice::Iter<'a, (DateTime, Vec<u32>)>);
+pub struct Iter<'a> {
+    matrix: &'a Matrix,
+    range: Range<usize>,
+}
 
 impl<'a> Iterator for Iter<'a> {
-    type Item = (DateTime, &'a Row);
+    type Item = (DateTime, Row<'a>);
 
     fn next(&mut self) -> Option<Self::Item> {
-        self.0
-            .next()
-            .map(|(timestamp, data)| (*timestamp, Row::ref_cast(data)))
+        let index = self.range.next()?;
+        let row = Row {
+            matrix: self.matrix,
+            index,
+        };
+        Some((self.matrix.timestamps[index], row))
     }
 }
 
 impl<'a> DoubleEndedIterator for Iter<'a> {
     fn next_back(&mut self) -> Option<Self::Item> {
-        self.0
-            .next_back()
-            .map(|(timestamp, data)| (*timestamp, Row::ref_cast(data)))
+        let index = self.range.next_back()?;
+        let row = Row {
+            matrix: self.matrix,
+            index,
+        };
+        Some((self.matrix.timestamps[index], row))
+    }
+}
+
+impl<'a> Row<'a> {
+    pub fn len(&self) -> usize {
+        self.matrix.width()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
     }
 }
 
-impl Index<usize> for Row {
+impl<'a> Index<usize> for Row<'a> {
     type Output = u32;
 
     fn index(&self, i: usize) -> &Self::Output {
-        &self.0[i]
+        let column = &self.matrix.columns[i];
+        let changes = column.partition_point(|&(row, _value)| row as usize <= self.index);
+        match changes.checked_sub(1) {
+            Some(last) => &column[last].1,
+            None => &0,
+        }
     }
 }
 
-impl<'a> IntoIterator for &'a Row {
+impl<'a> IntoIterator for Row<'a> {
     type Item = u32;
-    type IntoIter = Copied<slice::Iter<'a, u32>>;
+    type IntoIter = RowIter<'a>;
 
     fn into_iter(self) -> Self::IntoIter {
-        self.0.iter().copied()
+        RowIter {
+            row: self,
+            range: 0..self.len(),
+        }
     }
 }
 
-impl Deref for Row {
-    type Target = [u32];
+pub struct RowIter<'a> {
+    row: Row<'a>,
+    range: Range<usize>,
+}
+
+impl<'a> Iterator for RowIter<'a> {
+    type Item = u32;
 
-    fn deref(&self) -> &Self::Target {
-        &self.0
+    fn next(&mut self) -> Option<Self::Item> {
+        let i = self.range.next()?;
+        Some(self.row[i])
     }
 }
 
 pub struct RelativeRow<'a> {
-    row: &'a Row,
+    row: Row<'a>,
     total: u32,
 }
 
-impl<'a> Div<u32> for &'a Row {
+impl<'a> Div<u32> for Row<'a> {
     type Output = RelativeRow<'a>;
 
     fn div(self, rhs: u32) -> Self::Output {
@@ -113,9 +247,9 @@ impl<'a> Div<u32> for &'a Row {
     }
 }
 
-impl Debug for Row {
+impl<'a> Debug for Row<'a> {
     fn fmt(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
-        formatter.debug_list().entries(&self.0).finish()
+        formatter.debug_list().entries(*self).finish()
     }
 }
 
@@ -128,3 +262,109 @@ impl<'a> Debug for RelativeRow<'a> {
         list.finish()
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::Matrix;
+    use crate::timestamp::{DateTime, Interval};
+    use chrono::{NaiveDate, NaiveTime};
+
+    fn date(string: &str) -> DateTime {
+        DateTime::new(string.parse::<NaiveDate>().unwrap(), NaiveTime::MIN)
+    }
+
+    fn matrix(rows: &[(&str, &[u32])]) -> Matrix {
+        let mut matrix = Matrix::new(rows[0].1.len());
+        for (timestamp, values) in rows {
+            matrix.push(date(timestamp), values.iter().copied().enumerate());
+        }
+        matrix
+    }
+
+    fn contents(matrix: &Matrix) -> Vec<(String, Vec<u32>)> {
+        matrix
+            .iter()
+            .map(|(timestamp, row)| {
+                let date = timestamp.to_rfc3339()[..10].to_owned();
+                (date, row.into_iter().collect())
+            })
+            .collect()
+    }
+
+    fn expected(rows: &[(&str, &[u32])]) -> Vec<(String, Vec<u32>)> {
+        rows.iter()
+            .map(|(date, values)| ((*date).to_owned(), values.to_vec()))
+            .collect()
+    }
+
+    #[test]
+    fn test_select() {
+        let mut m = matrix(&[
+            ("2022-01-01", &[1, 0]),
+            ("2022-01-02", &[2, 0]),
+            ("2022-01-03", &[2, 5]),
+            ("2022-01-04", &[3, 5]),
+        ]);
+        m.select(vec![
+            (date("2022-02-01"), 0),
+            (date("2022-02-02"), 0),
+            (date("2022-02-03"), 2),
+        ]);
+        let rows = expected(&[
+            ("2022-02-01", &[1, 0]),
+            ("2022-02-02", &[1, 0]),
+            ("2022-02-03", &[2, 5]),
+        ]);
+        assert_eq!(contents(&m), rows);
+        assert_eq!(m.column(0).count(), 2);
+        assert_eq!(m.column(1).count(), 1);
+    }
+
+    #[test]
+    fn test_clip() {
+        let mut m = matrix(&[
+            ("2022-01-01", &[1]),
+            ("2022-01-05", &[2]),
+            ("2022-01-10", &[3]),
+            ("2022-01-20", &[4]),
+        ]);
+        m.clip(Some(date("2022-01-07")), Some(date("2022-01-20")));
+        let rows = expected(&[("2022-01-07", &[2]), ("2022-01-10", &[3])]);
+        assert_eq!(contents(&m), rows);
+
+        let mut m = matrix(&[("2022-01-05", &[1]), ("2022-01-10", &[2])]);
+        m.clip(Some(date("2022-01-01")), None);
+        let rows = expected(&[("2022-01-05", &[1]), ("2022-01-10", &[2])]);
+        assert_eq!(contents(&m), rows);
+    }
+
+    #[test]
+    fn test_resample() {
+        let mut m = matrix(&[
+            ("2022-01-01", &[1]),
+            ("2022-01-15", &[2]),
+            ("2022-01-20", &[3]),
+            ("2022-03-10", &[4]),
+        ]);
+        m.resample(Interval::Month, date("2022-05-15"));
+        let rows = expected(&[
+            ("2022-01-01", &[1]),
+            ("2022-02-01", &[3]),
+            ("2022-03-01", &[3]),
+            ("2022-04-01", &[4]),
+            ("2022-05-01", &[4]),
+            ("2022-05-15", &[4]),
+        ]);
+        assert_eq!(contents(&m), rows);
+    }
+
+    #[test]
+    fn test_resample_flat() {
+        let mut m = matrix(&[("2021-06-01", &[7])]);
+        m.clip(Some(date("2022-01-01")), Some(date("2023-01-01")));
+        m.resample(Interval::Month, date("2023-01-01"));
+        let values: Vec<u32> = m.iter().map(|(_timestamp, row)| row[0]).collect();
+        assert_eq!(values, [7; 13]);
+        assert_eq!(m.iter().next_back().unwrap().0, date("2023-01-01"));
+    }
+}
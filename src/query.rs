@@ -1,140 +1,771 @@
 use crate::cratemap::CrateMap;
+use crate::syntax::{self, Expr, Leaf, ParseError};
 use crate::user::UserQuery;
 use anyhow::{bail, format_err, Error, Result};
 use cargo_tally::arena::Slice;
-use cargo_tally::id::QueryId;
+use cargo_tally::dependency::{DependencyKind, DependencyKinds};
+use cargo_tally::feature::{DefaultFeatures, FeatureNames};
+use cargo_tally::id::{CrateId, QueryId, TermId};
 use cargo_tally::version::VersionReq;
-use cargo_tally::{Predicate, Query};
+use cargo_tally::{Op, Predicate, Query, Release};
 use ref_cast::RefCast;
+use regex::Regex;
+use semver::{BuildMetadata, Version};
+use std::collections::{BTreeMap as Map, BTreeSet as Set};
 use std::fmt::{self, Display};
-use std::str::{FromStr, Split};
+use std::str::FromStr;
 
-// for example &["serde:1.0", "anyhow:^1.0 + thiserror"]
+// for example &["serde:1.0", "anyhow:^1.0 + thiserror", "tokio & !async-std"]
 pub fn parse<'a>(
     queries: impl IntoIterator<Item = &'a str>,
     crates: &CrateMap,
+    features: &FeatureNames,
 ) -> Result<Vec<Query>> {
     queries
         .into_iter()
         .enumerate()
         .map(|(i, query)| {
-            let id = QueryId(u8::try_from(i).unwrap());
-            match parse_predicates(query, crates) {
-                Ok(predicates) => Ok(Query { id, predicates }),
-                Err(err) => bail!("failed to parse query {:?}: {}", query, err),
+            let id = query_id(i)?;
+            match parse_query(query, crates, features) {
+                Ok((terms, expr)) => Ok(Query {
+                    id,
+                    terms,
+                    expr,
+                    depth: None,
+                }),
+                Err(err) => bail!("failed to parse query: {}", err),
             }
         })
         .collect()
 }
 
-fn parse_predicates(string: &str, crates: &CrateMap) -> Result<Slice<Predicate>> {
-    let mut predicates = Vec::new();
+pub fn query_id(i: usize) -> Result<QueryId> {
+    match u32::try_from(i) {
+        Ok(id) => Ok(QueryId(id)),
+        Err(_) => bail!("too many queries, the most supported is {}", u32::MAX),
+    }
+}
+
+fn term_id(i: usize) -> Result<TermId> {
+    match u32::try_from(i) {
+        Ok(id) => Ok(TermId(id)),
+        Err(_) => bail!(
+            "too many terms in one query, the most supported is {}",
+            u32::MAX
+        ),
+    }
+}
+
+fn parse_query(
+    query: &str,
+    crates: &CrateMap,
+    features: &FeatureNames,
+) -> Result<(Slice<Slice<Predicate>>, Slice<Op>)> {
+    let expr = syntax::parse(query)?;
+    let mut terms = Vec::new();
+    let mut ops = Vec::new();
+    compile(&expr, &mut terms, &mut ops)?;
+
+    let terms = terms
+        .iter()
+        .map(|leaves| {
+            let mut predicates = Vec::new();
+            for &leaf in leaves {
+                parse_predicates(leaf.text, crates, features, &mut predicates)
+                    .map_err(|err| ParseError::at_leaf(query, leaf, err))?;
+            }
+            Ok(Slice::new(&predicates))
+        })
+        .collect::<Result<Vec<_>, ParseError>>()?;
+
+    Ok((Slice::new(&terms), Slice::new(&ops)))
+}
+
+// Postfix form of the expression, made of terms that are each either a single
+// predicate or several joined by `+`.
+fn compile<'a>(expr: &Expr<'a>, terms: &mut Vec<Vec<Leaf<'a>>>, ops: &mut Vec<Op>) -> Result<()> {
+    if let Some(leaves) = expr.disjunction() {
+        let term_id = term_id(terms.len())?;
+        terms.push(leaves);
+        ops.push(Op::Term(term_id));
+        return Ok(());
+    }
+    match expr {
+        Expr::Predicate(_) => unreachable!(),
+        Expr::Not(expr) => {
+            compile(expr, terms, ops)?;
+            ops.push(Op::Not);
+        }
+        Expr::And(lhs, rhs) => {
+            compile(lhs, terms, ops)?;
+            compile(rhs, terms, ops)?;
+            ops.push(Op::And);
+        }
+        Expr::Or(lhs, rhs) => {
+            compile(lhs, terms, ops)?;
+            compile(rhs, terms, ops)?;
+            ops.push(Op::Or);
+        }
+    }
+    Ok(())
+}
 
-    for predicate in IterPredicates::new(string, crates) {
-        let predicate = predicate?;
-        match predicate {
-            RawPredicate::Crate(predicate) => predicates.push(predicate),
-            RawPredicate::User(username) => {
-                let Some(user_id) = crates.users.get(username) else {
-                    let kind = if username.is_team() { "team" } else { "user" };
-                    bail!("no crates owned by {} @{}", kind, username);
+fn parse_predicates(
+    string: &str,
+    crates: &CrateMap,
+    features: &FeatureNames,
+    predicates: &mut Vec<Predicate>,
+) -> Result<()> {
+    match RawPredicate::parse(string, crates)? {
+        RawPredicate::Crate(mut predicate, feature) => {
+            if let Some(feature) = feature {
+                let Some(feature_id) = features.get(feature) else {
+                    bail!("no crate has a feature named {}", feature);
                 };
-                predicates.extend(
-                    crates
-                        .owners
-                        .get(user_id)
-                        .map(Vec::as_slice)
-                        .unwrap_or_default()
-                        .iter()
-                        .map(|&crate_id| Predicate {
-                            crate_id,
-                            req: None,
-                        }),
-                );
+                predicate.feature = Some(feature_id);
+            }
+            predicates.push(predicate);
+        }
+        RawPredicate::Pattern(pattern, regex, kinds) => {
+            let len = predicates.len();
+            predicates.extend(
+                crates
+                    .iter()
+                    .filter(|(_crate_id, name)| regex.is_match(name))
+                    .map(|(crate_id, _name)| Predicate {
+                        crate_id,
+                        req: None,
+                        kinds,
+                        feature: None,
+                        default_features: None,
+                    }),
+            );
+            if predicates.len() == len {
+                bail!("no crate name matches {}", pattern);
+            }
+        }
+        RawPredicate::Category(slug, kinds) => {
+            // A category includes its subcategories.
+            let subcategory = format!("{}::", slug);
+            let crate_ids: Set<CrateId> = crates
+                .categories
+                .iter()
+                .filter(|(other, _crate_ids)| *other == slug || other.starts_with(&subcategory))
+                .flat_map(|(_slug, crate_ids)| crate_ids)
+                .copied()
+                .collect();
+            if crate_ids.is_empty() {
+                bail!("no crates in category {}", slug);
+            }
+            predicates.extend(crate_ids.into_iter().map(|crate_id| Predicate {
+                crate_id,
+                req: None,
+                kinds,
+                feature: None,
+                default_features: None,
+            }));
+        }
+        RawPredicate::Keyword(keyword, kinds) => {
+            let Some(crate_ids) = crates.keywords.get(keyword) else {
+                bail!("no crates with keyword {}", keyword);
+            };
+            predicates.extend(crate_ids.iter().map(|&crate_id| Predicate {
+                crate_id,
+                req: None,
+                kinds,
+                feature: None,
+                default_features: None,
+            }));
+        }
+        RawPredicate::User(username, kinds) => {
+            let Some(user_id) = crates.users.get(username) else {
+                let kind = if username.is_team() { "team" } else { "user" };
+                bail!("no crates owned by {} @{}", kind, username);
+            };
+            predicates.extend(
+                crates
+                    .owners
+                    .get(user_id)
+                    .map(Vec::as_slice)
+                    .unwrap_or_default()
+                    .iter()
+                    .map(|&crate_id| Predicate {
+                        crate_id,
+                        req: None,
+                        kinds,
+                        feature: None,
+                        default_features: None,
+                    }),
+            );
+        }
+    }
+    Ok(())
+}
+
+// Every query twice over, counting first the dependents that keep the default
+// features of every crate it names and then the ones that turn them off for at
+// least one. Each dependent counted by the query lands in exactly one of the
+// two, by way of an extra term satisfied by any dependency on those crates with
+// default features off.
+pub fn split_default_features(
+    queries: &[Query],
+    labels: &[String],
+) -> Result<(Vec<Query>, Vec<String>)> {
+    let mut split_queries = Vec::new();
+    let mut split_labels = Vec::new();
+    for (query, label) in queries.iter().zip(labels) {
+        let disables: Vec<Predicate> = query
+            .terms
+            .iter()
+            .flatten()
+            .map(|pred| Predicate {
+                default_features: Some(DefaultFeatures(false)),
+                ..pred
+            })
+            .collect();
+        let mut terms: Vec<Slice<Predicate>> = query.terms.iter().collect();
+        let disables_term = term_id(terms.len())?;
+        terms.push(Slice::new(&disables));
+        let terms = Slice::new(&terms);
+        for (keeps, suffix) in [(true, "default features"), (false, "no default features")] {
+            let id = query_id(split_queries.len())?;
+            let mut expr: Vec<Op> = query.expr.iter().collect();
+            expr.push(Op::Term(disables_term));
+            if keeps {
+                expr.push(Op::Not);
             }
+            expr.push(Op::And);
+            split_queries.push(Query {
+                id,
+                terms,
+                expr: Slice::new(&expr),
+                depth: query.depth,
+            });
+            split_labels.push(format!("{} ({})", label, suffix));
         }
     }
+    Ok((split_queries, split_labels))
+}
 
-    Ok(Slice::new(&predicates))
+// Every query once for each depth, counting only the dependents within that
+// many dependencies of it.
+pub fn split_depth(
+    queries: &[Query],
+    labels: &[String],
+    depths: &[u16],
+) -> Result<(Vec<Query>, Vec<String>)> {
+    let mut split_queries = Vec::new();
+    let mut split_labels = Vec::new();
+    for (query, label) in queries.iter().zip(labels) {
+        for &depth in depths {
+            let id = query_id(split_queries.len())?;
+            split_queries.push(Query {
+                id,
+                depth: Some(depth),
+                ..*query
+            });
+            split_labels.push(format!("{} (depth {})", label, depth));
+        }
+    }
+    Ok((split_queries, split_labels))
+}
+
+// Every query, which must name a single crate like `regex`, once for each line
+// of semver-compatible releases of that crate, like `regex:0.1`, `regex:0.2` and
+// `regex:1`. Prereleases do not start a line of their own.
+pub fn split_major(
+    queries: &[String],
+    crates: &CrateMap,
+    releases: &[Release],
+) -> Result<Vec<String>> {
+    split_releases(queries, crates, releases, "--by-major", |version| {
+        if !version.pre.is_empty() {
+            return None;
+        }
+        Some(match (version.major, version.minor, version.patch) {
+            (0, 0, patch) => (Version::new(0, 0, patch), format!("0.0.{}", patch)),
+            (0, minor, _patch) => (Version::new(0, minor, 0), format!("0.{}", minor)),
+            (major, _minor, _patch) => (Version::new(major, 0, 0), major.to_string()),
+        })
+    })
+}
+
+// Every query, which must name a single crate like `regex`, once for each
+// release of that crate, like `regex:=1.5.0`. Dependencies count toward the
+// release that their requirement resolves to, so this is how many dependents
+// would get each release of the crate if they were built at that time.
+pub fn split_resolved(
+    queries: &[String],
+    crates: &CrateMap,
+    releases: &[Release],
+) -> Result<Vec<String>> {
+    split_releases(queries, crates, releases, "--by-version", |version| {
+        let version = Version {
+            build: BuildMetadata::EMPTY,
+            ..version.clone()
+        };
+        let req = format!("={}", version);
+        Some((version, req))
+    })
+}
+
+fn split_releases(
+    queries: &[String],
+    crates: &CrateMap,
+    releases: &[Release],
+    flag: &str,
+    line: impl Fn(&Version) -> Option<(Version, String)>,
+) -> Result<Vec<String>> {
+    let mut split_queries = Vec::new();
+    for query in queries {
+        let expr = syntax::parse(query).unwrap();
+        let crate_id = match expr {
+            Expr::Predicate(leaf) => match RawPredicate::parse(leaf.text, crates)? {
+                RawPredicate::Crate(predicate, _feature) if predicate.req.is_none() => {
+                    Some(predicate.crate_id)
+                }
+                _ => None,
+            },
+            _ => None,
+        };
+        let Some(crate_id) = crate_id else {
+            bail!(
+                "{} needs each query to be a single crate without a version requirement, not {}",
+                flag,
+                query,
+            );
+        };
+
+        let lines: Map<Version, String> = releases
+            .iter()
+            .filter(|rel| rel.crate_id == crate_id)
+            .filter_map(|rel| line(&rel.num))
+            .collect();
+        if lines.is_empty() {
+            split_queries.push(query.clone());
+            continue;
+        }
+
+        // Splice the version requirement in between the crate name and any
+        // feature or dependency kinds, as in `serde:1/derive[normal]`.
+        let query = query.trim();
+        let kinds = match query.strip_suffix(']') {
+            Some(rest) => rest.rfind('[').unwrap(),
+            None => query.len(),
+        };
+        let feature = query[..kinds].find('/').unwrap_or(kinds);
+        let name = query[..feature].trim_end();
+        for req in lines.into_values() {
+            split_queries.push(format!("{}:{}{}", name, req, &query[feature..]));
+        }
+    }
+    Ok(split_queries)
 }
 
 pub fn format(query: &str, crates: &CrateMap) -> String {
-    DisplayQuery { query, crates }.to_string()
+    let expr = syntax::parse(query).unwrap();
+    DisplayExpr {
+        expr: &expr,
+        crates,
+        parent: Precedence::Or,
+    }
+    .to_string()
+}
+
+// Label of each term of the query, in the same order as Query::terms.
+pub fn format_terms(query: &str, crates: &CrateMap) -> Vec<String> {
+    let expr = syntax::parse(query).unwrap();
+    let mut terms = Vec::new();
+    compile(&expr, &mut terms, &mut Vec::new()).unwrap();
+    terms
+        .into_iter()
+        .map(|leaves| {
+            let mut label = String::new();
+            for (i, leaf) in leaves.into_iter().enumerate() {
+                if i > 0 {
+                    label.push_str(" or ");
+                }
+                label.push_str(&DisplayLeaf { leaf, crates }.to_string());
+            }
+            label
+        })
+        .collect()
 }
 
-struct DisplayQuery<'a> {
-    query: &'a str,
+#[derive(Copy, Clone, PartialEq, PartialOrd)]
+enum Precedence {
+    Or,
+    And,
+    Not,
+}
+
+struct DisplayExpr<'a> {
+    expr: &'a Expr<'a>,
     crates: &'a CrateMap,
+    // Precedence of the operator that this expression is an operand of.
+    parent: Precedence,
 }
 
-impl<'a> Display for DisplayQuery<'a> {
+impl<'a> DisplayExpr<'a> {
+    fn operand(&self, expr: &'a Expr<'a>, parent: Precedence) -> Self {
+        DisplayExpr {
+            expr,
+            crates: self.crates,
+            parent,
+        }
+    }
+}
+
+impl<'a> Display for DisplayExpr<'a> {
     fn fmt(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
-        for (i, predicate) in IterPredicates::new(self.query, self.crates).enumerate() {
-            if i > 0 {
-                formatter.write_str(" or ")?;
-            }
-
-            let predicate = predicate.unwrap();
-            match predicate {
-                RawPredicate::Crate(predicate) => {
-                    let original_name = self.crates.name(predicate.crate_id).unwrap();
-                    formatter.write_str(original_name)?;
-                    if let Some(req) = predicate.req {
-                        write!(formatter, ":{}", req)?;
-                    }
+        let precedence = match self.expr {
+            Expr::Predicate(leaf) => {
+                let crates = self.crates;
+                return Display::fmt(
+                    &DisplayLeaf {
+                        leaf: *leaf,
+                        crates,
+                    },
+                    formatter,
+                );
+            }
+            Expr::Not(_) => Precedence::Not,
+            Expr::And(..) => Precedence::And,
+            Expr::Or(..) => Precedence::Or,
+        };
+        let parenthesize = precedence < self.parent;
+        if parenthesize {
+            formatter.write_str("(")?;
+        }
+        match self.expr {
+            Expr::Predicate(_) => unreachable!(),
+            Expr::Not(expr) => {
+                write!(formatter, "not {}", self.operand(expr, Precedence::Not))?;
+            }
+            Expr::And(lhs, rhs) => write!(
+                formatter,
+                "{} and {}",
+                self.operand(lhs, Precedence::And),
+                self.operand(rhs, Precedence::And),
+            )?,
+            Expr::Or(lhs, rhs) => write!(
+                formatter,
+                "{} or {}",
+                self.operand(lhs, Precedence::Or),
+                self.operand(rhs, Precedence::Or),
+            )?,
+        }
+        if parenthesize {
+            formatter.write_str(")")?;
+        }
+        Ok(())
+    }
+}
+
+struct DisplayLeaf<'a> {
+    leaf: Leaf<'a>,
+    crates: &'a CrateMap,
+}
+
+impl<'a> Display for DisplayLeaf<'a> {
+    fn fmt(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        let predicate = RawPredicate::parse(self.leaf.text, self.crates).unwrap();
+        let kinds = match predicate {
+            RawPredicate::Crate(predicate, feature) => {
+                let original_name = self.crates.name(predicate.crate_id).unwrap();
+                formatter.write_str(original_name)?;
+                if let Some(req) = predicate.req {
+                    write!(formatter, ":{}", req)?;
                 }
-                RawPredicate::User(username) => {
-                    let (username, _user_id) = self.crates.users.get_key_value(username).unwrap();
-                    write!(formatter, "@{}", username)?;
+                if let Some(feature) = feature {
+                    write!(formatter, "/{}", feature)?;
                 }
+                predicate.kinds
+            }
+            RawPredicate::Pattern(pattern, _regex, kinds) => {
+                formatter.write_str(pattern)?;
+                kinds
+            }
+            RawPredicate::Category(slug, kinds) => {
+                write!(formatter, "category:{}", slug)?;
+                kinds
             }
+            RawPredicate::Keyword(keyword, kinds) => {
+                write!(formatter, "keyword:{}", keyword)?;
+                kinds
+            }
+            RawPredicate::User(username, kinds) => {
+                let (username, _user_id) = self.crates.users.get_key_value(username).unwrap();
+                write!(formatter, "@{}", username)?;
+                kinds
+            }
+        };
+        if let Some(kinds) = kinds {
+            write!(formatter, "[{}]", kinds)?;
         }
         Ok(())
     }
 }
 
 enum RawPredicate<'a> {
-    Crate(Predicate),
-    User(&'a UserQuery),
+    Crate(Predicate, Option<&'a str>),
+    // `glob:` or `re:` followed by a pattern, and the regex that crate names
+    // must match.
+    Pattern(&'a str, Regex, Option<DependencyKinds>),
+    Category(&'a str, Option<DependencyKinds>),
+    Keyword(&'a str, Option<DependencyKinds>),
+    User(&'a UserQuery, Option<DependencyKinds>),
 }
 
-struct IterPredicates<'a> {
-    split: Split<'a, char>,
-    crates: &'a CrateMap,
-}
+impl<'a> RawPredicate<'a> {
+    fn parse(predicate: &'a str, crates: &CrateMap) -> Result<Self> {
+        // Dependency kinds come after the closing slash of a regex, so that
+        // brackets inside the regex are not mistaken for them.
+        if predicate.starts_with("re:/") {
+            let Some((regex, suffix)) = syntax::split_regex(predicate) else {
+                bail!("expected a regex between slashes, like re:/^tokio-/");
+            };
+            let (rest, kinds) = split_kinds(suffix.trim_start())?;
+            if !rest.is_empty() {
+                bail!("unexpected {:?} after regex", rest);
+            }
+            let pattern = &predicate[..predicate.len() - suffix.len()];
+            let regex = Regex::new(regex)?;
+            return Ok(RawPredicate::Pattern(pattern, regex, kinds));
+        }
 
-impl<'a> IterPredicates<'a> {
-    fn new(query: &'a str, crates: &'a CrateMap) -> Self {
-        IterPredicates {
-            split: query.split('+'),
-            crates,
+        // Without slashes, trailing brackets are dependency kinds only if
+        // that is all they contain, and otherwise part of the regex.
+        if let Some(regex) = predicate.strip_prefix("re:") {
+            let (pattern, kinds) = split_kinds(predicate).unwrap_or((predicate, None));
+            let regex = &regex[..pattern.len() - "re:".len()];
+            if regex.contains(syntax::is_operator) {
+                bail!(
+                    "a regex containing '+', '&', '!', parentheses or spaces must be \
+                     written between slashes, like re:/^(a|b)$/",
+                );
+            }
+            let regex = Regex::new(regex)?;
+            return Ok(RawPredicate::Pattern(pattern, regex, kinds));
+        }
+
+        let (predicate, kinds) = split_kinds(predicate)?;
+
+        if let Some(glob) = predicate.strip_prefix("glob:") {
+            let regex = glob_to_regex(glob);
+            return Ok(RawPredicate::Pattern(predicate, regex, kinds));
         }
-    }
-}
 
-impl<'a> Iterator for IterPredicates<'a> {
-    type Item = Result<RawPredicate<'a>>;
+        if let Some(slug) = predicate.strip_prefix("category:") {
+            return Ok(RawPredicate::Category(slug, kinds));
+        }
 
-    fn next(&mut self) -> Option<Self::Item> {
-        let predicate = self.split.next()?.trim();
+        if let Some(keyword) = predicate.strip_prefix("keyword:") {
+            return Ok(RawPredicate::Keyword(keyword, kinds));
+        }
 
         if let Some(username) = predicate.strip_prefix('@') {
-            return Some(Ok(RawPredicate::User(UserQuery::ref_cast(username))));
+            let username = UserQuery::ref_cast(username);
+            return Ok(RawPredicate::User(username, kinds));
         }
 
+        let (predicate, feature) = match predicate.split_once('/') {
+            Some((predicate, feature)) => (predicate.trim_end(), Some(feature.trim_start())),
+            None => (predicate, None),
+        };
+
         let (name, req) = if let Some((name, req)) = predicate.split_once(':') {
-            match VersionReq::from_str(req) {
-                Ok(req) => (name, Some(req)),
-                Err(err) => return Some(Err(Error::new(err))),
-            }
+            (name, Some(VersionReq::from_str(req).map_err(Error::new)?))
         } else {
             (predicate, None)
         };
 
-        let Some(crate_id) = self.crates.id(name) else {
-            return Some(Err(format_err!("no crate named {}", name)));
+        let Some(crate_id) = crates.id(name) else {
+            bail!("no crate named {}", name);
+        };
+
+        let predicate = Predicate {
+            crate_id,
+            req,
+            kinds,
+            feature: None,
+            default_features: None,
+        };
+        Ok(RawPredicate::Crate(predicate, feature))
+    }
+}
+
+// for example "tokio-*", which must match the whole crate name, with '-' and
+// '_' interchangeable the same as when looking up a crate by name
+fn glob_to_regex(glob: &str) -> Regex {
+    let mut regex = String::from("^");
+    for ch in glob.chars() {
+        match ch {
+            '*' => regex.push_str(".*"),
+            '?' => regex.push('.'),
+            '-' | '_' => regex.push_str("[-_]"),
+            _ => regex.push_str(&regex::escape(ch.encode_utf8(&mut [0; 4]))),
+        }
+    }
+    regex.push('$');
+    Regex::new(&regex).unwrap()
+}
+
+// for example "criterion:0.5[dev]" or "cc[build,normal]"
+fn split_kinds(predicate: &str) -> Result<(&str, Option<DependencyKinds>)> {
+    let Some(rest) = predicate.strip_suffix(']') else {
+        return Ok((predicate, None));
+    };
+    let Some((predicate, kinds)) = rest.rsplit_once('[') else {
+        bail!("unmatched ']' in {:?}", predicate);
+    };
+    let kinds = kinds
+        .split(',')
+        .map(|kind| {
+            let kind = kind.trim();
+            DependencyKind::from_str(kind).map_err(|()| {
+                format_err!(
+                    "unknown dependency kind {:?}, expected normal, build or dev",
+                    kind,
+                )
+            })
+        })
+        .collect::<Result<DependencyKinds>>()?;
+    Ok((predicate.trim_end(), Some(kinds)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::RawPredicate;
+    use crate::common::{self, Builder};
+    use crate::cratemap::CrateMap;
+    use cargo_tally::dependency::DependencyKind;
+    use cargo_tally::feature::{DefaultFeatures, FeatureNames};
+    use cargo_tally::id::{CrateId, TermId};
+    use cargo_tally::{DbDump, Op, Query};
+
+    const SERDE: CrateId = CrateId(1);
+    const ANYHOW: CrateId = CrateId(2);
+
+    #[test]
+    fn test_regex_brackets() {
+        let crates = CrateMap::new();
+
+        let predicate = RawPredicate::parse("re:/^tokio[0-9]/", &crates).unwrap();
+        let RawPredicate::Pattern(pattern, regex, kinds) = predicate else {
+            panic!();
+        };
+        assert_eq!(pattern, "re:/^tokio[0-9]/");
+        assert!(regex.is_match("tokio1"));
+        assert!(!regex.is_match("tokio-util"));
+        assert_eq!(kinds, None);
+
+        let predicate = RawPredicate::parse("re:/^tokio[0-9]/[dev]", &crates).unwrap();
+        let RawPredicate::Pattern(pattern, _regex, kinds) = predicate else {
+            panic!();
+        };
+        assert_eq!(pattern, "re:/^tokio[0-9]/");
+        assert_eq!(kinds, Some([DependencyKind::Dev].into_iter().collect()));
+
+        assert!(RawPredicate::parse("re:/^tokio/serde", &crates).is_err());
+
+        let predicate = RawPredicate::parse("re:^tokio[0-9]", &crates).unwrap();
+        let RawPredicate::Pattern(pattern, regex, kinds) = predicate else {
+            panic!();
+        };
+        assert_eq!(pattern, "re:^tokio[0-9]");
+        assert!(regex.is_match("tokio1"));
+        assert_eq!(kinds, None);
+
+        let predicate = RawPredicate::parse("re:^tokio[0-9][build, dev]", &crates).unwrap();
+        let RawPredicate::Pattern(pattern, _regex, kinds) = predicate else {
+            panic!();
         };
+        assert_eq!(pattern, "re:^tokio[0-9]");
+        let expected = [DependencyKind::Build, DependencyKind::Dev];
+        assert_eq!(kinds, Some(expected.into_iter().collect()));
+
+        assert!(RawPredicate::parse("re:^a b", &crates).is_err());
+    }
+
+    #[test]
+    fn test_regex_unslashed() {
+        let mut crates = CrateMap::new();
+        let names = ["aws-config", "aws-sdk-ec2", "aws-sdk-s3", "serde"];
+        for (crate_id, name) in (1..).zip(names) {
+            crates.insert(CrateId(crate_id), name.to_owned());
+        }
+        let features = FeatureNames::new();
+
+        let queries = super::parse(["re:^aws-sdk-", "re:/^aws-sdk-/"], &crates, &features).unwrap();
+        for query in &queries {
+            let [term] = query.terms.iter().collect::<Vec<_>>()[..] else {
+                panic!();
+            };
+            let crate_ids: Vec<CrateId> = term.iter().map(|predicate| predicate.crate_id).collect();
+            assert_eq!(crate_ids, [CrateId(2), CrateId(3)]);
+        }
+    }
 
-        Some(Ok(RawPredicate::Crate(Predicate { crate_id, req })))
+    // Serde and anyhow, and crates depending on them with or without default
+    // features, one of which depends on serde both ways.
+    fn db_dump() -> DbDump {
+        let dependents: [&[(CrateId, DependencyKind, bool)]; 6] = [
+            &[],
+            &[],
+            &[
+                (SERDE, DependencyKind::Normal, true),
+                (ANYHOW, DependencyKind::Normal, true),
+            ],
+            &[
+                (SERDE, DependencyKind::Normal, false),
+                (ANYHOW, DependencyKind::Normal, true),
+            ],
+            &[
+                (SERDE, DependencyKind::Normal, true),
+                (SERDE, DependencyKind::Dev, false),
+            ],
+            &[(ANYHOW, DependencyKind::Normal, false)],
+        ];
+        let mut builder = Builder::default();
+        for (crate_id, dependencies) in (1..).zip(dependents) {
+            let version_id = builder.release(CrateId(crate_id), &[]);
+            for &(dependency, kind, default_features) in dependencies {
+                let dependency = builder.dependency(version_id, dependency, None, &[]);
+                dependency.kind = kind;
+                dependency.default_features = DefaultFeatures(default_features);
+            }
+        }
+        builder.db_dump
+    }
+
+    fn final_values(queries: &[Query]) -> Vec<u32> {
+        common::final_values(db_dump(), common::config(false), queries)
+    }
+
+    #[test]
+    fn test_split_default_features_sum() {
+        let serde = [common::predicate(SERDE)];
+        let anyhow = [common::predicate(ANYHOW)];
+        let either = [common::predicate(SERDE), common::predicate(ANYHOW)];
+        let term = |i| Op::Term(TermId(i));
+        // serde, serde + anyhow, serde & anyhow, serde & !anyhow
+        let queries = [
+            common::query(0, &[&serde], &[term(0)]),
+            common::query(1, &[&either], &[term(0)]),
+            common::query(2, &[&serde, &anyhow], &[term(0), term(1), Op::And]),
+            common::query(3, &[&serde, &anyhow], &[term(0), term(1), Op::Not, Op::And]),
+        ];
+        let labels = vec![String::new(); queries.len()];
+
+        let unsplit = final_values(&queries);
+        assert_eq!(unsplit, [3, 4, 2, 1]);
+
+        let (split, _labels) = super::split_default_features(&queries, &labels).unwrap();
+        let split = final_values(&split);
+        assert_eq!(split, [1, 2, 1, 3, 1, 1, 0, 1]);
+        for (i, value) in unsplit.into_iter().enumerate() {
+            assert_eq!(split[2 * i] + split[2 * i + 1], value);
+        }
     }
 }
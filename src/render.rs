@@ -1,3 +1,5 @@
+use crate::args::Opt;
+use crate::format::JsonString;
 use crate::total::Total;
 use anyhow::Result;
 use cargo_tally::matrix::Matrix;
@@ -6,62 +8,55 @@ use std::cmp;
 use std::env;
 use std::fmt::{self, Display};
 use std::fs;
-use std::path::PathBuf;
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub(crate) enum Plot {
+    Html,
+    Term,
+}
+
+impl Plot {
+    pub(crate) const NAMES: [&'static str; 2] = ["html", "term"];
+}
+
+impl FromStr for Plot {
+    type Err = ();
+
+    fn from_str(string: &str) -> Result<Self, Self::Err> {
+        match string {
+            "html" => Ok(Plot::Html),
+            "term" => Ok(Plot::Term),
+            _ => Err(()),
+        }
+    }
+}
 
 pub(crate) fn graph(
-    title: Option<&str>,
-    transitive: bool,
+    opt: &Opt,
     results: &Matrix,
     labels: &[String],
     total: Option<&Total>,
-) -> Result<PathBuf> {
+) -> Result<String> {
     let now = DateTime::now();
+    let end = opt.until.map_or(now, |until| cmp::min(until, now));
 
     let relative = total.is_some();
-    let title = if let Some(title) = title {
-        title
-    } else if relative {
-        if transitive {
-            "fraction of crates.io depending transitively"
-        } else {
-            "fraction of crates.io depending directly"
-        }
-    } else {
-        if transitive {
-            "number of crates depending transitively"
-        } else {
-            "number of crates depending directly"
-        }
-    };
+    let title = title(opt);
 
     let mut data = String::new();
     data += "[\n";
     for (i, label) in labels.iter().enumerate() {
-        data += "      {\"name\":\"";
-        data += label;
-        data += "\", \"values\":[\n";
-        let mut prev = None;
-        for (timestamp, row) in results {
-            let value = row[i];
-            if prev.is_none() {
-                if value == 0 {
-                    continue;
-                }
-                let mut secs = timestamp.seconds();
-                if timestamp.subsec_nanos() == 0 {
-                    secs = secs.saturating_sub(1);
-                }
-                let timestamp = DateTime::from_timestamp(secs, 0);
-                data += &Row(timestamp, 0, total).to_string();
-            } else if prev == Some(value) {
-                continue;
-            }
+        // Escaped as a JSON string, and with "</" broken up so that a label
+        // cannot close the <script> element it is embedded in.
+        let name = JsonString(label).to_string().replace("</", "<\\/");
+        data += "      {\"name\":";
+        data += &name;
+        data += ", \"values\":[\n";
+        for (timestamp, value) in series(results, i, opt.since, end) {
             data += &Row(timestamp, value, total).to_string();
-            prev = Some(value);
-        }
-        let (timestamp, last) = results.iter().next_back().unwrap();
-        if timestamp < now {
-            data += &Row(now, last[i], total).to_string();
         }
         data += "      ]},\n";
     }
@@ -72,14 +67,114 @@ pub(crate) fn graph(
     preprocessor_context
         .define("CARGO_TALLY_TITLE", format!("\"{}\"", title.escape_debug()))
         .define("CARGO_TALLY_DATA", data)
-        .define("CARGO_TALLY_RELATIVE", (relative as usize).to_string());
+        .define("CARGO_TALLY_RELATIVE", (relative as usize).to_string())
+        .define("CARGO_TALLY_CDN", (opt.cdn as usize).to_string())
+        .define("CARGO_TALLY_D3", include_str!("d3.js"));
     let html = minipre::process_str(template, &mut preprocessor_context)?;
+    Ok(html)
+}
+
+// Writes the graph to the path given by --output, or to stdout if that path is
+// "-", or else to a new file in the temp directory. Returns the path of the
+// file written, if any.
+pub(crate) fn write(contents: &str, output: Option<&Path>) -> Result<Option<PathBuf>> {
+    let path = match output {
+        Some(path) if path == Path::new("-") => {
+            let mut stdout = io::stdout().lock();
+            stdout.write_all(contents.as_bytes())?;
+            stdout.flush()?;
+            return Ok(None);
+        }
+        Some(path) => path.to_owned(),
+        None => {
+            let dir = env::temp_dir().join("cargo-tally");
+            fs::create_dir_all(&dir)?;
+            dir.join(format!("{}.html", DateTime::now().millis()))
+        }
+    };
+    fs::write(&path, contents)?;
+    Ok(Some(path))
+}
+
+pub(crate) fn title(opt: &Opt) -> &str {
+    if let Some(title) = &opt.title {
+        title
+    } else if opt.dependencies_of {
+        match (opt.relative, opt.transitive) {
+            (true, true) => "transitive dependencies as a fraction of crates.io",
+            (true, false) => "direct dependencies as a fraction of crates.io",
+            (false, true) => "number of transitive dependencies",
+            (false, false) => "number of direct dependencies",
+        }
+    } else if opt.relative {
+        if opt.transitive {
+            "fraction of crates.io depending transitively"
+        } else {
+            "fraction of crates.io depending directly"
+        }
+    } else {
+        if opt.transitive {
+            "number of crates depending transitively"
+        } else {
+            "number of crates depending directly"
+        }
+    }
+}
+
+// The points at which column i of the results changes value, starting from
+// zero just before its first nonzero value unless that value was already in
+// effect at `since`, and held flat until `end`.
+pub(crate) fn series(
+    results: &Matrix,
+    i: usize,
+    since: Option<DateTime>,
+    end: DateTime,
+) -> Vec<(DateTime, u32)> {
+    let mut points = Vec::new();
+    let mut prev = None;
+    for (timestamp, value) in results.column(i) {
+        if prev.is_none() {
+            if value == 0 {
+                continue;
+            }
+            if since.is_some_and(|since| timestamp <= since) {
+                points.push((timestamp, value));
+                prev = Some(value);
+                continue;
+            }
+            let mut secs = timestamp.seconds();
+            if timestamp.subsec_nanos() == 0 {
+                secs = secs.saturating_sub(1);
+            }
+            points.push((DateTime::from_timestamp(secs, 0), 0));
+        } else if prev == Some(value) {
+            continue;
+        }
+        points.push((timestamp, value));
+        prev = Some(value);
+    }
+    if points
+        .last()
+        .is_none_or(|&(timestamp, _value)| timestamp < end)
+    {
+        points.push((end, prev.unwrap_or(0)));
+    }
+    points
+}
 
-    let dir = env::temp_dir().join("cargo-tally");
-    fs::create_dir_all(&dir)?;
-    let path = dir.join(format!("{}.html", now.millis()));
-    fs::write(&path, html)?;
-    Ok(path)
+pub(crate) fn fraction(value: u32, total: u32) -> f32 {
+    if total == 0 {
+        0.0
+    } else if value == total {
+        // Bump a 100% down to 50%. The only graph affected by this is
+        // `cargo tally --relative --transitive @alexcrichton` and while 50% is
+        // not an accurate datum, this hack makes that graph more readable by
+        // avoiding the y-axis getting extended all the way to 100% in the
+        // first day of crates.io's existence.
+        0.5
+    } else {
+        value as f32 / total as f32
+    }
 }
 
 struct Row<'a>(DateTime, u32, Option<&'a Total>);
@@ -90,20 +185,8 @@ impl<'a> Display for Row<'a> {
         write!(formatter, "{}", self.0.millis())?;
         formatter.write_str(", \"edges\":")?;
         if let Some(total) = self.2 {
-            let total = total.eval(self.0);
-            if total == 0 {
-                formatter.write_str("0")?;
-            } else if self.1 == total {
-                // Bump a 100% down to 50%. The only graph affected by this is
-                // `cargo tally --relative --transitive @alexcrichton` and while
-                // 50% is not an accurate datum, this hack makes that graph more
-                // readable by avoiding the y-axis getting extended all the way
-                // to 100% in the first day of crates.io's existence.
-                formatter.write_str("0.5")?;
-            } else {
-                let fraction = self.1 as f32 / total as f32;
-                write_truncated(formatter, fraction)?;
-            }
+            let fraction = fraction(self.1, total.eval(self.0));
+            write_truncated(formatter, fraction)?;
         } else {
             write!(formatter, "{}", self.1)?;
         }
@@ -123,3 +206,31 @@ fn write_truncated(formatter: &mut fmt::Formatter, fraction: f32) -> fmt::Result
     }
     formatter.write_str(&repr)
 }
+
+#[cfg(test)]
+mod tests {
+    use super::graph;
+    use crate::args;
+    use cargo_tally::matrix::Matrix;
+    use cargo_tally::timestamp::DateTime;
+    use chrono::{NaiveDate, NaiveTime};
+
+    fn render(args: &[&str]) -> String {
+        let opt = args::parse_from(args.iter().map(Into::into));
+        let mut results = Matrix::new(1);
+        let date = NaiveDate::from_ymd_opt(2022, 1, 1).unwrap();
+        results.push(DateTime::new(date, NaiveTime::MIN), [(0, 1)]);
+        graph(&opt, &results, &opt.queries, None).unwrap()
+    }
+
+    #[test]
+    fn test_embedded_d3() {
+        let html = render(&["cargo-tally", "serde"]);
+        assert!(html.contains("var d3 = "));
+        assert!(!html.contains("https://d3js.org"));
+
+        let html = render(&["cargo-tally", "--cdn", "serde"]);
+        assert!(!html.contains("var d3 = "));
+        assert!(html.contains("<script src=\"https://d3js.org/d3.v7.min.js\"></script>"));
+    }
+}
@@ -0,0 +1,561 @@
+// Static rendering of the same graph as index.html, for places that cannot run
+// its script. The layout, scales, tick choice, and number formatting follow
+// what D3 does in index.html, except that dates are laid out in UTC rather than
+// the viewer's local time zone, and the curves end at the last change in the
+// data (or at --until if that has already passed) rather than at the current
+// time, so that the output depends only on the input.
+
+use crate::args::Opt;
+use crate::render;
+use crate::total::Total;
+use cargo_tally::matrix::Matrix;
+use chrono::{DateTime, Datelike, Duration, NaiveDateTime, Timelike};
+use std::fmt::Write;
+
+const WIDTH: f64 = 800.0;
+const HEIGHT: f64 = 450.0;
+const MARGIN_TOP: f64 = 20.0;
+const MARGIN_RIGHT: f64 = 100.0;
+const MARGIN_BOTTOM: f64 = 30.0;
+const MARGIN_LEFT: f64 = 50.0;
+
+const COLORS: [&str; 10] = [
+    "#1f77b4", "#ff7f0e", "#2ca02c", "#d62728", "#9467bd", "#8c564b", "#e377c2", "#7f7f7f",
+    "#bcbd22", "#17becf",
+];
+
+pub(crate) fn graph(
+    opt: &Opt,
+    results: &Matrix,
+    labels: &[String],
+    total: Option<&Total>,
+) -> String {
+    let relative = total.is_some();
+    let title = render::title(opt);
+
+    let (last, _row) = results.iter().next_back().unwrap();
+    let end = opt
+        .until
+        .filter(|until| *until <= cargo_tally::timestamp::DateTime::now())
+        .unwrap_or(last);
+    let data: Vec<Vec<(f64, f64)>> = (0..labels.len())
+        .map(|i| {
+            render::series(results, i, opt.since, end)
+                .into_iter()
+                .map(|(timestamp, value)| {
+                    let value = match total {
+                        Some(total) => f64::from(render::fraction(value, total.eval(timestamp))),
+                        None => f64::from(value),
+                    };
+                    (timestamp.millis() as f64, value)
+                })
+                .collect()
+        })
+        .collect();
+
+    let min_date = data
+        .iter()
+        .map(|values| values[0].0)
+        .fold(f64::INFINITY, f64::min);
+    let max_date = data
+        .iter()
+        .map(|values| values[values.len() - 1].0)
+        .fold(f64::NEG_INFINITY, f64::max);
+    let max_value = data
+        .iter()
+        .flatten()
+        .map(|&(_time, value)| value)
+        .fold(0.0, f64::max);
+    let x = Scale {
+        domain: ((21.0 * min_date - max_date) / 20.0, max_date),
+        range: (0.0, WIDTH),
+    };
+    let y = Scale {
+        domain: (0.0, 1.025 * max_value),
+        range: (HEIGHT, 0.0),
+    };
+
+    let y_ticks = ticks(y.domain.0, y.domain.1, 10.0);
+    let y_format = tick_format(relative, &y_ticks);
+
+    let mut svg = String::new();
+    let _ = writeln!(
+        svg,
+        "<svg xmlns=\"http://www.w3.org/2000/svg\" width=\"{w}\" height=\"{h}\" viewBox=\"0 0 {w} {h}\" font-family=\"sans-serif\" font-size=\"14\">",
+        w = WIDTH + MARGIN_LEFT + MARGIN_RIGHT,
+        h = HEIGHT + MARGIN_TOP + MARGIN_BOTTOM,
+    );
+    svg += "<rect width=\"100%\" height=\"100%\" fill=\"white\"/>\n";
+    let _ = writeln!(
+        svg,
+        "<g transform=\"translate({} {})\">",
+        MARGIN_LEFT, MARGIN_TOP,
+    );
+
+    for (i, label) in labels.iter().enumerate() {
+        let _ = writeln!(
+            svg,
+            "<rect x=\"50\" y=\"{}\" width=\"10\" height=\"10\" fill=\"{}\"/>",
+            i * 20,
+            COLORS[i % COLORS.len()],
+        );
+        let _ = writeln!(
+            svg,
+            "<text x=\"64\" y=\"{}\">{}</text>",
+            i * 20 + 9,
+            escape(label),
+        );
+    }
+
+    let x_ticks = time_ticks(x.domain.0, x.domain.1, 10.0);
+    let _ = writeln!(
+        svg,
+        "<g transform=\"translate(0 {})\" font-size=\"10\" text-anchor=\"middle\">",
+        HEIGHT,
+    );
+    let _ = writeln!(
+        svg,
+        "<path stroke=\"black\" fill=\"none\" shape-rendering=\"crispEdges\" d=\"M{},6V0.5H{}V6\"/>",
+        number(x.range.0 + 0.5),
+        number(x.range.1 + 0.5),
+    );
+    for (time, label) in x_ticks {
+        let _ = writeln!(
+            svg,
+            "<g transform=\"translate({} 0)\"><line stroke=\"black\" shape-rendering=\"crispEdges\" y2=\"6\"/><text y=\"9\" dy=\"0.71em\">{}</text></g>",
+            number(x.apply(time) + 0.5),
+            label,
+        );
+    }
+    svg += "</g>\n";
+
+    svg += "<g font-size=\"10\" text-anchor=\"end\">\n";
+    let _ = writeln!(
+        svg,
+        "<path stroke=\"black\" fill=\"none\" shape-rendering=\"crispEdges\" d=\"M-6,{}H0.5V{}H-6\"/>",
+        number(y.range.0 + 0.5),
+        number(y.range.1 + 0.5),
+    );
+    for value in y_ticks {
+        let _ = writeln!(
+            svg,
+            "<g transform=\"translate(0 {})\"><line stroke=\"black\" shape-rendering=\"crispEdges\" x2=\"-6\"/><text x=\"-9\" dy=\"0.32em\">{}</text></g>",
+            number(y.apply(value) + 0.5),
+            y_format(value),
+        );
+    }
+    svg += "</g>\n";
+
+    let _ = writeln!(
+        svg,
+        "<text transform=\"rotate(-90)\" y=\"6\" dy=\".71em\" text-anchor=\"end\">{}</text>",
+        escape(title),
+    );
+
+    for (i, (label, values)) in labels.iter().zip(&data).enumerate() {
+        svg += "<path fill=\"none\" stroke-width=\"1.5\" stroke-linejoin=\"round\" stroke=\"";
+        svg += COLORS[i % COLORS.len()];
+        svg += "\" d=\"";
+        for (j, &(time, value)) in values.iter().enumerate() {
+            let command = if j == 0 { 'M' } else { 'L' };
+            let _ = write!(
+                svg,
+                "{}{},{}",
+                command,
+                number(x.apply(time)),
+                number(y.apply(value)),
+            );
+        }
+        svg += "\"/>\n";
+        let &(time, value) = values.last().unwrap();
+        let _ = writeln!(
+            svg,
+            "<text transform=\"translate({} {})\" x=\"3\" dy=\".35em\">{}</text>",
+            number(x.apply(time)),
+            number(y.apply(value)),
+            escape(label),
+        );
+    }
+
+    svg += "</g>\n</svg>\n";
+    svg
+}
+
+struct Scale {
+    domain: (f64, f64),
+    range: (f64, f64),
+}
+
+impl Scale {
+    fn apply(&self, value: f64) -> f64 {
+        let (d0, d1) = self.domain;
+        let (r0, r1) = self.range;
+        let t = if d1 > d0 {
+            (value - d0) / (d1 - d0)
+        } else {
+            0.5
+        };
+        r0 + t * (r1 - r0)
+    }
+}
+
+fn tick_spec(start: f64, end: f64, count: f64) -> (f64, f64, f64) {
+    let step = (end - start) / count.max(0.0);
+    let power = step.log10().floor();
+    let error = step / 10f64.powf(power);
+    let factor = if error >= 50f64.sqrt() {
+        10.0
+    } else if error >= 10f64.sqrt() {
+        5.0
+    } else if error >= 2f64.sqrt() {
+        2.0
+    } else {
+        1.0
+    };
+    let (mut i1, mut i2, inc);
+    if power < 0.0 {
+        let positive_inc = 10f64.powf(-power) / factor;
+        i1 = (start * positive_inc).round();
+        i2 = (end * positive_inc).round();
+        if i1 / positive_inc < start {
+            i1 += 1.0;
+        }
+        if i2 / positive_inc > end {
+            i2 -= 1.0;
+        }
+        inc = -positive_inc;
+    } else {
+        inc = 10f64.powf(power) * factor;
+        i1 = (start / inc).round();
+        i2 = (end / inc).round();
+        if i1 * inc < start {
+            i1 += 1.0;
+        }
+        if i2 * inc > end {
+            i2 -= 1.0;
+        }
+    }
+    if i2 < i1 && 0.5 <= count && count < 2.0 {
+        return tick_spec(start, end, count * 2.0);
+    }
+    (i1, i2, inc)
+}
+
+pub(crate) fn ticks(start: f64, end: f64, count: f64) -> Vec<f64> {
+    if end <= start {
+        return if end < start { Vec::new() } else { vec![start] };
+    }
+    let (i1, i2, inc) = tick_spec(start, end, count);
+    let mut ticks = Vec::new();
+    let mut i = i1;
+    while i <= i2 {
+        ticks.push(if inc < 0.0 { i / -inc } else { i * inc });
+        i += 1.0;
+    }
+    ticks
+}
+
+fn tick_step(start: f64, end: f64, count: f64) -> f64 {
+    let (_i1, _i2, inc) = tick_spec(start, end, count);
+    if inc < 0.0 {
+        -1.0 / inc
+    } else {
+        inc
+    }
+}
+
+// Like d3's default tick format for the y axis in index.html: grouped integers,
+// or percentages with just enough precision to tell the ticks apart.
+pub(crate) fn tick_format(relative: bool, ticks: &[f64]) -> Box<dyn Fn(f64) -> String> {
+    if relative {
+        let step = match ticks {
+            [first, second, ..] => second - first,
+            _ => 1.0,
+        };
+        let precision = precision_fixed(step).saturating_sub(2);
+        Box::new(move |value| format!("{:.*}%", precision, value * 100.0))
+    } else {
+        Box::new(format_grouped)
+    }
+}
+
+fn precision_fixed(step: f64) -> usize {
+    let exponent = if step == 0.0 || !step.is_finite() {
+        0
+    } else {
+        let repr = format!("{:e}", step.abs());
+        repr[repr.find('e').unwrap() + 1..].parse::<i32>().unwrap()
+    };
+    usize::try_from(-exponent).unwrap_or(0)
+}
+
+// Like d3.format(","): up to 12 significant digits, with thousands separators.
+fn format_grouped(value: f64) -> String {
+    let rounded: f64 = format!("{:.11e}", value).parse().unwrap();
+    let repr = rounded.to_string();
+    let (integer, fraction) = match repr.find('.') {
+        Some(dot) => repr.split_at(dot),
+        None => (repr.as_str(), ""),
+    };
+    let (sign, digits) = match integer.strip_prefix('-') {
+        Some(digits) => ("\u{2212}", digits),
+        None => ("", integer),
+    };
+    let mut grouped = String::new();
+    for (i, ch) in digits.chars().enumerate() {
+        if i > 0 && (digits.len() - i) % 3 == 0 {
+            grouped.push(',');
+        }
+        grouped.push(ch);
+    }
+    format!("{}{}{}", sign, grouped, fraction)
+}
+
+#[derive(Copy, Clone)]
+enum Unit {
+    Second,
+    Minute,
+    Hour,
+    Day,
+    Week,
+    Month,
+    Year,
+}
+
+const SECOND: f64 = 1e3;
+const MINUTE: f64 = SECOND * 60.0;
+const HOUR: f64 = MINUTE * 60.0;
+const DAY: f64 = HOUR * 24.0;
+const WEEK: f64 = DAY * 7.0;
+const MONTH: f64 = DAY * 30.0;
+const YEAR: f64 = DAY * 365.0;
+
+const TICK_INTERVALS: [(Unit, i64, f64); 18] = [
+    (Unit::Second, 1, SECOND),
+    (Unit::Second, 5, 5.0 * SECOND),
+    (Unit::Second, 15, 15.0 * SECOND),
+    (Unit::Second, 30, 30.0 * SECOND),
+    (Unit::Minute, 1, MINUTE),
+    (Unit::Minute, 5, 5.0 * MINUTE),
+    (Unit::Minute, 15, 15.0 * MINUTE),
+    (Unit::Minute, 30, 30.0 * MINUTE),
+    (Unit::Hour, 1, HOUR),
+    (Unit::Hour, 3, 3.0 * HOUR),
+    (Unit::Hour, 6, 6.0 * HOUR),
+    (Unit::Hour, 12, 12.0 * HOUR),
+    (Unit::Day, 1, DAY),
+    (Unit::Day, 2, 2.0 * DAY),
+    (Unit::Week, 1, WEEK),
+    (Unit::Month, 1, MONTH),
+    (Unit::Month, 3, 3.0 * MONTH),
+    (Unit::Year, 1, YEAR),
+];
+
+impl Unit {
+    fn floor(self, date: NaiveDateTime) -> NaiveDateTime {
+        let midnight = date.date().and_hms_opt(0, 0, 0).unwrap();
+        match self {
+            Unit::Second => date.with_nanosecond(0).unwrap(),
+            Unit::Minute => date.with_nanosecond(0).unwrap().with_second(0).unwrap(),
+            Unit::Hour => date.date().and_hms_opt(date.hour(), 0, 0).unwrap(),
+            Unit::Day => midnight,
+            Unit::Week => {
+                midnight - Duration::days(i64::from(date.weekday().num_days_from_sunday()))
+            }
+            Unit::Month => midnight.with_day(1).unwrap(),
+            Unit::Year => midnight.with_ordinal(1).unwrap(),
+        }
+    }
+
+    fn offset(self, date: NaiveDateTime) -> NaiveDateTime {
+        match self {
+            Unit::Second => date + Duration::seconds(1),
+            Unit::Minute => date + Duration::minutes(1),
+            Unit::Hour => date + Duration::hours(1),
+            Unit::Day => date + Duration::days(1),
+            Unit::Week => date + Duration::weeks(1),
+            Unit::Month => {
+                let (year, month) = if date.month() == 12 {
+                    (date.year() + 1, 1)
+                } else {
+                    (date.year(), date.month() + 1)
+                };
+                date.with_day(1)
+                    .unwrap()
+                    .with_year(year)
+                    .unwrap()
+                    .with_month(month)
+                    .unwrap()
+            }
+            Unit::Year => date.with_year(date.year() + 1).unwrap(),
+        }
+    }
+
+    fn field(self, date: NaiveDateTime) -> i64 {
+        i64::from(match self {
+            Unit::Second => date.second(),
+            Unit::Minute => date.minute(),
+            Unit::Hour => date.hour(),
+            Unit::Day => date.day() - 1,
+            Unit::Week => 0,
+            Unit::Month => date.month0(),
+            Unit::Year => return i64::from(date.year()),
+        })
+    }
+
+    // Dates in [start, end) which are a boundary of this unit and whose
+    // field is a multiple of step.
+    fn range(self, step: i64, start: f64, end: f64) -> Vec<NaiveDateTime> {
+        let mut dates = Vec::new();
+        let mut date = self.offset(self.floor(naive(start - 1.0)));
+        while millis(date) < end {
+            if step <= 1 || self.field(date) % step == 0 {
+                dates.push(date);
+            }
+            date = self.floor(self.offset(date));
+        }
+        dates
+    }
+
+    fn is_boundary(self, date: NaiveDateTime) -> bool {
+        self.floor(date) == date
+    }
+}
+
+pub(crate) fn time_ticks(start: f64, end: f64, count: f64) -> Vec<(f64, String)> {
+    let target = (end - start).abs() / count;
+    let i = TICK_INTERVALS
+        .iter()
+        .position(|&(_unit, _step, duration)| duration > target)
+        .unwrap_or(TICK_INTERVALS.len());
+    let dates = if i == TICK_INTERVALS.len() {
+        let step = tick_step(start / YEAR, end / YEAR, count).floor() as i64;
+        Unit::Year.range(step, start, end + 1.0)
+    } else if i == 0 {
+        ticks(start, end, count).into_iter().map(naive).collect()
+    } else {
+        let i = if target / TICK_INTERVALS[i - 1].2 < TICK_INTERVALS[i].2 / target {
+            i - 1
+        } else {
+            i
+        };
+        let (unit, step, _duration) = TICK_INTERVALS[i];
+        unit.range(step, start, end + 1.0)
+    };
+    dates
+        .into_iter()
+        .map(|date| (millis(date), format_time(date)))
+        .collect()
+}
+
+// Same choice of format as the default tick format of d3.scaleTime.
+fn format_time(date: NaiveDateTime) -> String {
+    let format = if !Unit::Second.is_boundary(date) {
+        return format!(".{:03}", date.nanosecond() / 1_000_000);
+    } else if !Unit::Minute.is_boundary(date) {
+        ":%S"
+    } else if !Unit::Hour.is_boundary(date) {
+        "%I:%M"
+    } else if !Unit::Day.is_boundary(date) {
+        "%I %p"
+    } else if !Unit::Month.is_boundary(date) {
+        if Unit::Week.is_boundary(date) {
+            "%b %d"
+        } else {
+            "%a %d"
+        }
+    } else if !Unit::Year.is_boundary(date) {
+        "%B"
+    } else {
+        "%Y"
+    };
+    date.format(format).to_string()
+}
+
+fn naive(millis: f64) -> NaiveDateTime {
+    DateTime::from_timestamp_millis(millis.floor() as i64)
+        .unwrap()
+        .naive_utc()
+}
+
+fn millis(date: NaiveDateTime) -> f64 {
+    date.and_utc().timestamp_millis() as f64
+}
+
+fn number(value: f64) -> String {
+    let mut repr = format!("{:.2}", value);
+    while repr.ends_with('0') {
+        repr.pop();
+    }
+    if repr.ends_with('.') {
+        repr.pop();
+    }
+    if repr == "-0" {
+        repr.remove(0);
+    }
+    repr
+}
+
+fn escape(string: &str) -> String {
+    string
+        .replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::graph;
+    use crate::args;
+    use cargo_tally::matrix::Matrix;
+    use cargo_tally::timestamp::DateTime;
+    use chrono::{NaiveDate, NaiveTime};
+
+    fn render(args: &[&str]) -> String {
+        let opt = args::parse_from(args.iter().map(Into::into));
+        let mut results = Matrix::new(1);
+        for (day, value) in [(1, 1), (11, 3)] {
+            let date = NaiveDate::from_ymd_opt(2022, 1, day).unwrap();
+            results.push(DateTime::new(date, NaiveTime::MIN), [(0, value)]);
+        }
+        graph(&opt, &results, &opt.queries, None)
+    }
+
+    #[test]
+    fn test_deterministic() {
+        let args = ["cargo-tally", "--format", "svg", "serde"];
+        assert_eq!(render(&args), render(&args));
+
+        // an --until that has not happened yet ends at the data, not today
+        let args = [
+            "cargo-tally",
+            "--format",
+            "svg",
+            "--until",
+            "2999-12-31",
+            "serde",
+        ];
+        assert_eq!(
+            render(&args),
+            render(&["cargo-tally", "--format", "svg", "serde"])
+        );
+    }
+
+    #[test]
+    fn test_small_input() {
+        let svg = render(&["cargo-tally", "--format", "svg", "serde"]);
+        // 0 just before the 1st, 1 on the 1st and 3 on the 11th, with the
+        // y axis going up to 3 plus 2.5%
+        let path = "<path fill=\"none\" stroke-width=\"1.5\" stroke-linejoin=\"round\" \
+                    stroke=\"#1f77b4\" d=\"M38.1,450L38.1,303.66L800,10.98\"/>\n";
+        assert!(svg.contains(path));
+        assert!(svg.contains(">number of crates depending directly</text>"));
+        assert!(svg.contains(
+            "<text transform=\"translate(800 10.98)\" x=\"3\" dy=\".35em\">serde</text>"
+        ));
+        assert!(svg.contains("<text y=\"9\" dy=\"0.71em\">Tue 11</text>"));
+        assert!(svg.contains("<text x=\"-9\" dy=\"0.32em\">3</text>"));
+        assert!(svg.ends_with("</g>\n</svg>\n"));
+    }
+}
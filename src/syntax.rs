@@ -0,0 +1,306 @@
+// Grammar of a query, from loosest to tightest binding:
+//
+//     query := and ('+' and)*
+//     and   := not ('&' not)*
+//     not   := '!' not | '(' query ')' | predicate
+//
+// where a predicate is anything up to the next operator or parenthesis, like
+// `serde:1.0/derive[normal]` or `@dtolnay`. A regex predicate like
+// `re:^aws-sdk-` ends the same way, so a regex that needs the operator
+// characters or whitespace is delimited by slashes instead, like
+// `re:/^(a|b)$/`, and a slash in it is written `\/`.
+
+use std::fmt::{self, Display};
+
+pub(crate) enum Expr<'a> {
+    Predicate(Leaf<'a>),
+    Not(Box<Expr<'a>>),
+    And(Box<Expr<'a>>, Box<Expr<'a>>),
+    Or(Box<Expr<'a>>, Box<Expr<'a>>),
+}
+
+#[derive(Copy, Clone)]
+pub(crate) struct Leaf<'a> {
+    pub text: &'a str,
+    // Byte offset of the predicate in the query.
+    pub offset: usize,
+}
+
+impl<'a> Expr<'a> {
+    pub(crate) fn leaves(&self) -> Vec<Leaf<'a>> {
+        let mut leaves = Vec::new();
+        self.visit_leaves(&mut leaves);
+        leaves
+    }
+
+    fn visit_leaves(&self, leaves: &mut Vec<Leaf<'a>>) {
+        match self {
+            Expr::Predicate(leaf) => leaves.push(*leaf),
+            Expr::Not(expr) => expr.visit_leaves(leaves),
+            Expr::And(lhs, rhs) | Expr::Or(lhs, rhs) => {
+                lhs.visit_leaves(leaves);
+                rhs.visit_leaves(leaves);
+            }
+        }
+    }
+
+    // The predicates of an expression made of nothing but `+`, which all
+    // together make a single term of the query.
+    pub(crate) fn disjunction(&self) -> Option<Vec<Leaf<'a>>> {
+        match self {
+            Expr::Predicate(leaf) => Some(vec![*leaf]),
+            Expr::Or(lhs, rhs) => {
+                let mut leaves = lhs.disjunction()?;
+                leaves.extend(rhs.disjunction()?);
+                Some(leaves)
+            }
+            Expr::Not(_) | Expr::And(..) => None,
+        }
+    }
+}
+
+pub(crate) fn parse(query: &str) -> Result<Expr, ParseError> {
+    let mut parser = Parser { query, offset: 0 };
+    let expr = parser.parse_or()?;
+    let (offset, token) = parser.peek();
+    match token {
+        Token::End => Ok(expr),
+        Token::Close => Err(parser.error(offset, token, "unmatched ')'")),
+        _ => Err(parser.error(offset, token, "expected '+' or '&'")),
+    }
+}
+
+// For a predicate starting with a regex like `re:/^tokio-/`, the regex between
+// the slashes and whatever follows the closing slash.
+pub(crate) fn split_regex(predicate: &str) -> Option<(&str, &str)> {
+    let rest = predicate.strip_prefix("re:/")?;
+    let mut escaped = false;
+    for (i, ch) in rest.char_indices() {
+        match ch {
+            _ if escaped => escaped = false,
+            '\\' => escaped = true,
+            '/' => return Some((&rest[..i], &rest[i + 1..])),
+            _ => {}
+        }
+    }
+    None
+}
+
+// Characters that cannot appear in a regex predicate unless it is between
+// slashes.
+pub(crate) fn is_operator(ch: char) -> bool {
+    matches!(ch, '+' | '&' | '!' | '(' | ')') || ch.is_whitespace()
+}
+
+#[derive(Copy, Clone)]
+enum Token<'a> {
+    Or,
+    And,
+    Not,
+    Open,
+    Close,
+    Predicate(&'a str),
+    End,
+}
+
+impl<'a> Token<'a> {
+    fn len(self) -> usize {
+        match self {
+            Token::Or | Token::And | Token::Not | Token::Open | Token::Close => 1,
+            Token::Predicate(text) => text.len(),
+            Token::End => 0,
+        }
+    }
+}
+
+struct Parser<'a> {
+    query: &'a str,
+    offset: usize,
+}
+
+impl<'a> Parser<'a> {
+    fn peek(&self) -> (usize, Token<'a>) {
+        let rest = self.query[self.offset..].trim_start();
+        let offset = self.query.len() - rest.len();
+        let token = match rest.chars().next() {
+            None => Token::End,
+            Some('+') => Token::Or,
+            Some('&') => Token::And,
+            Some('!') => Token::Not,
+            Some('(') => Token::Open,
+            Some(')') => Token::Close,
+            Some(_) => {
+                let start = match split_regex(rest) {
+                    Some((_regex, suffix)) => rest.len() - suffix.len(),
+                    None => 0,
+                };
+                let end = rest[start..]
+                    .find(['+', '&', '!', '(', ')'])
+                    .map_or(rest.len(), |end| start + end);
+                Token::Predicate(rest[..end].trim_end())
+            }
+        };
+        (offset, token)
+    }
+
+    fn bump(&mut self, offset: usize, token: Token) {
+        self.offset = offset + token.len();
+    }
+
+    fn parse_or(&mut self) -> Result<Expr<'a>, ParseError> {
+        let mut expr = self.parse_and()?;
+        loop {
+            let (offset, token) = self.peek();
+            let Token::Or = token else {
+                return Ok(expr);
+            };
+            self.bump(offset, token);
+            let rhs = self.parse_and()?;
+            expr = Expr::Or(Box::new(expr), Box::new(rhs));
+        }
+    }
+
+    fn parse_and(&mut self) -> Result<Expr<'a>, ParseError> {
+        let mut expr = self.parse_not()?;
+        loop {
+            let (offset, token) = self.peek();
+            let Token::And = token else {
+                return Ok(expr);
+            };
+            self.bump(offset, token);
+            let rhs = self.parse_not()?;
+            expr = Expr::And(Box::new(expr), Box::new(rhs));
+        }
+    }
+
+    fn parse_not(&mut self) -> Result<Expr<'a>, ParseError> {
+        let (offset, token) = self.peek();
+        match token {
+            Token::Not => {
+                self.bump(offset, token);
+                let expr = self.parse_not()?;
+                Ok(Expr::Not(Box::new(expr)))
+            }
+            Token::Open => {
+                self.bump(offset, token);
+                let expr = self.parse_or()?;
+                let (close_offset, close) = self.peek();
+                match close {
+                    Token::Close => {
+                        self.bump(close_offset, close);
+                        Ok(expr)
+                    }
+                    Token::End => Err(self.error(offset, token, "unclosed '('")),
+                    _ => Err(self.error(close_offset, close, "expected '+', '&' or ')'")),
+                }
+            }
+            Token::Predicate(text) => {
+                self.bump(offset, token);
+                Ok(Expr::Predicate(Leaf { text, offset }))
+            }
+            Token::Or | Token::And | Token::Close | Token::End => {
+                Err(self.error(offset, token, "expected a crate, '!' or '('"))
+            }
+        }
+    }
+
+    fn error(&self, offset: usize, token: Token, message: &str) -> ParseError {
+        ParseError {
+            query: self.query.to_owned(),
+            offset,
+            len: token.len(),
+            message: message.to_owned(),
+        }
+    }
+}
+
+// An error in a query, displayed with the query underneath and a caret under
+// the part that is wrong.
+#[derive(Debug)]
+pub(crate) struct ParseError {
+    query: String,
+    offset: usize,
+    len: usize,
+    message: String,
+}
+
+impl ParseError {
+    pub(crate) fn at_leaf(query: &str, leaf: Leaf, message: impl Display) -> Self {
+        ParseError {
+            query: query.to_owned(),
+            offset: leaf.offset,
+            len: leaf.text.len(),
+            message: message.to_string(),
+        }
+    }
+}
+
+impl Display for ParseError {
+    fn fmt(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        let column = self.query[..self.offset].chars().count();
+        let width = self.query[self.offset..self.offset + self.len]
+            .chars()
+            .count()
+            .max(1);
+        write!(
+            formatter,
+            "{}\n    {}\n    {:column$}{}",
+            self.message,
+            self.query,
+            "",
+            "^".repeat(width),
+        )
+    }
+}
+
+impl std::error::Error for ParseError {}
+
+#[cfg(test)]
+mod tests {
+    use super::{parse, Expr};
+
+    fn sexpr(expr: &Expr) -> String {
+        match expr {
+            Expr::Predicate(leaf) => leaf.text.to_owned(),
+            Expr::Not(expr) => format!("(! {})", sexpr(expr)),
+            Expr::And(lhs, rhs) => format!("(& {} {})", sexpr(lhs), sexpr(rhs)),
+            Expr::Or(lhs, rhs) => format!("(+ {} {})", sexpr(lhs), sexpr(rhs)),
+        }
+    }
+
+    fn check(query: &str, expected: &str) {
+        let expr = parse(query).unwrap_or_else(|err| panic!("{}", err));
+        assert_eq!(sexpr(&expr), expected);
+    }
+
+    #[test]
+    fn test_operators() {
+        check(
+            "serde + anyhow & !thiserror",
+            "(+ serde (& anyhow (! thiserror)))",
+        );
+        check(
+            "(serde + anyhow) & thiserror",
+            "(& (+ serde anyhow) thiserror)",
+        );
+    }
+
+    #[test]
+    fn test_regex() {
+        check("re:^aws-sdk- + serde", "(+ re:^aws-sdk- serde)");
+        check("re:/^(a|b)+$/", "re:/^(a|b)+$/");
+        check("re:/^a+b/[dev]", "re:/^a+b/[dev]");
+        check("(re:/^tokio-(util)?$/)", "re:/^tokio-(util)?$/");
+        check("(re:/a b/)&serde", "(& re:/a b/ serde)");
+        check("!re:/^x&y$/+serde", "(+ (! re:/^x&y$/) serde)");
+        check(r"re:/^a\/b(c)/ & (re:/!/)", r"(& re:/^a\/b(c)/ re:/!/)");
+    }
+
+    #[test]
+    fn test_errors() {
+        assert!(parse("(re:/a)/").is_err());
+        assert!(parse("(serde").is_err());
+        assert!(parse("serde)").is_err());
+        assert!(parse("serde anyhow + ").is_err());
+    }
+}
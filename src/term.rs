@@ -0,0 +1,262 @@
+// Line chart drawn with braille characters, for `--plot term`. Every character
+// cell of the plot area holds a 2x4 grid of dots; cells crossed by more than
+// one query take the color of the last of them.
+
+use crate::args::Opt;
+use crate::log::Log;
+use crate::render;
+use crate::svg;
+use crate::total::Total;
+use cargo_tally::matrix::Matrix;
+use cargo_tally::timestamp::DateTime;
+use std::cmp;
+use std::env;
+use std::io::Write;
+use termcolor::StandardStream;
+use terminal_size::{Height, Width};
+
+pub(crate) fn draw(
+    stream: &mut StandardStream,
+    opt: &Opt,
+    results: &Matrix,
+    labels: &[String],
+    total: Option<&Total>,
+) {
+    let relative = total.is_some();
+    let title = render::title(opt);
+
+    let now = DateTime::now();
+    let end = opt.until.map_or(now, |until| cmp::min(until, now));
+    let data: Vec<Vec<(f64, f64)>> = (0..labels.len())
+        .map(|i| {
+            render::series(results, i, opt.since, end)
+                .into_iter()
+                .map(|(timestamp, value)| {
+                    let value = match total {
+                        Some(total) => f64::from(render::fraction(value, total.eval(timestamp))),
+                        None => f64::from(value),
+                    };
+                    (timestamp.millis() as f64, value)
+                })
+                .collect()
+        })
+        .collect();
+
+    let min_date = data
+        .iter()
+        .map(|values| values[0].0)
+        .fold(f64::INFINITY, f64::min);
+    let max_date = end.millis() as f64;
+    let max_value = data
+        .iter()
+        .flatten()
+        .map(|&(_time, value)| value)
+        .fold(0.0, f64::max);
+
+    let (columns, rows) = size();
+    let height = rows.saturating_sub(labels.len() + 4).clamp(8, 40);
+    let y_ticks = svg::ticks(0.0, max_value, (height / 3) as f64);
+    let y_format = svg::tick_format(relative, &y_ticks);
+    let y_labels: Vec<(usize, String)> = y_ticks
+        .iter()
+        .map(|&value| {
+            let row = if max_value > 0.0 {
+                (1.0 - value / max_value) * (height - 1) as f64
+            } else {
+                (height - 1) as f64
+            };
+            let row = usize::try_from(row.round() as isize).unwrap_or(0);
+            (row, y_format(value))
+        })
+        .collect();
+    let gutter = y_labels
+        .iter()
+        .map(|(_row, label)| label.chars().count())
+        .max()
+        .unwrap_or(0)
+        + 1;
+    let width = columns.saturating_sub(gutter + 2).max(10);
+
+    let mut canvas = Canvas::new(width, height);
+    let (dot_width, dot_height) = (canvas.dot_width(), canvas.dot_height());
+    let x_scale = |time: f64| {
+        if max_date > min_date {
+            (time - min_date) / (max_date - min_date) * (dot_width - 1) as f64
+        } else {
+            0.0
+        }
+    };
+    let y_scale = |value: f64| {
+        if max_value > 0.0 {
+            (1.0 - value / max_value) * (dot_height - 1) as f64
+        } else {
+            (dot_height - 1) as f64
+        }
+    };
+    let points: Vec<Vec<(isize, isize)>> = data
+        .iter()
+        .map(|values| {
+            values
+                .iter()
+                .map(|&(time, value)| {
+                    (
+                        x_scale(time).round() as isize,
+                        y_scale(value).round() as isize,
+                    )
+                })
+                .collect()
+        })
+        .collect();
+    for (i, points) in points.iter().enumerate() {
+        for pair in points.windows(2) {
+            canvas.line(pair[0], pair[1], i);
+        }
+        if let [point] = points.as_slice() {
+            canvas.line(*point, *point, i);
+        }
+    }
+
+    let _ = writeln!(stream, "{}", title);
+    for (i, label) in labels.iter().enumerate() {
+        write!(stream.series(i), "\u{25a0}");
+        let _ = writeln!(stream, " {}", label);
+    }
+    let _ = writeln!(stream);
+
+    for (row, cells) in canvas.cells.chunks(width).enumerate() {
+        let label = y_labels.iter().find(|(tick_row, _label)| *tick_row == row);
+        match label {
+            Some((_row, label)) => {
+                let _ = write!(stream, "{:>1$}\u{2524}", label, gutter - 1);
+            }
+            None => {
+                let _ = write!(stream, "{:1$}\u{2502}", "", gutter - 1);
+            }
+        }
+        for cell in cells {
+            let ch = char::from_u32(0x2800 + u32::from(cell.dots)).unwrap();
+            match cell.series {
+                Some(i) => write!(stream.series(i), "{}", ch),
+                None => {
+                    let _ = write!(stream, "{}", ch);
+                }
+            }
+        }
+        let _ = writeln!(stream);
+    }
+
+    let _ = write!(stream, "{:1$}\u{2514}", "", gutter - 1);
+    let mut axis = vec!['\u{2500}'; width];
+    let mut x_labels = vec![' '; width + gutter];
+    let mut free = 0;
+    let x_ticks = svg::time_ticks(min_date, max_date, (width / 12).max(1) as f64);
+    for (time, label) in x_ticks {
+        let Ok(column) = usize::try_from((x_scale(time) / 2.0).round() as isize) else {
+            continue;
+        };
+        if column >= width {
+            continue;
+        }
+        axis[column] = '\u{252c}';
+        let len = label.chars().count();
+        let start = (gutter + column).saturating_sub(len / 2);
+        if start < free || start + len > x_labels.len() {
+            continue;
+        }
+        for (j, ch) in label.chars().enumerate() {
+            x_labels[start + j] = ch;
+        }
+        free = start + len + 1;
+    }
+    let _ = writeln!(stream, "{}", axis.into_iter().collect::<String>());
+    let x_labels = x_labels.into_iter().collect::<String>();
+    let _ = writeln!(stream, "{}", x_labels.trim_end());
+    let _ = stream.flush();
+}
+
+// Bit for each dot position within a braille character, by [x][y].
+const BITS: [[u8; 4]; 2] = [[0x01, 0x02, 0x04, 0x40], [0x08, 0x10, 0x20, 0x80]];
+
+#[derive(Copy, Clone, Default)]
+struct Cell {
+    dots: u8,
+    series: Option<usize>,
+}
+
+struct Canvas {
+    width: usize,
+    height: usize,
+    cells: Vec<Cell>,
+}
+
+impl Canvas {
+    fn new(width: usize, height: usize) -> Self {
+        Canvas {
+            width,
+            height,
+            cells: vec![Cell::default(); width * height],
+        }
+    }
+
+    fn dot_width(&self) -> usize {
+        self.width * 2
+    }
+
+    fn dot_height(&self) -> usize {
+        self.height * 4
+    }
+
+    fn set(&mut self, x: isize, y: isize, series: usize) {
+        let (Ok(x), Ok(y)) = (usize::try_from(x), usize::try_from(y)) else {
+            return;
+        };
+        if x >= self.dot_width() || y >= self.dot_height() {
+            return;
+        }
+        let cell = &mut self.cells[y / 4 * self.width + x / 2];
+        cell.dots |= BITS[x % 2][y % 4];
+        cell.series = Some(series);
+    }
+
+    // Bresenham's line algorithm.
+    fn line(&mut self, from: (isize, isize), to: (isize, isize), series: usize) {
+        let (mut x, mut y) = from;
+        let dx = (to.0 - x).abs();
+        let dy = -(to.1 - y).abs();
+        let sx = if x < to.0 { 1 } else { -1 };
+        let sy = if y < to.1 { 1 } else { -1 };
+        let mut err = dx + dy;
+        loop {
+            self.set(x, y, series);
+            if (x, y) == to {
+                break;
+            }
+            let e2 = 2 * err;
+            if e2 >= dy {
+                err += dy;
+                x += sx;
+            }
+            if e2 <= dx {
+                err += dx;
+                y += sy;
+            }
+        }
+    }
+}
+
+// Columns and rows of the terminal on stdout, falling back to $COLUMNS and
+// $LINES, then 80x24, when stdout is not a terminal.
+fn size() -> (usize, usize) {
+    if let Some((Width(columns), Height(rows))) = terminal_size::terminal_size() {
+        if columns > 0 && rows > 0 {
+            return (usize::from(columns), usize::from(rows));
+        }
+    }
+    let var = |name: &str, default: usize| {
+        env::var(name)
+            .ok()
+            .and_then(|value| value.parse().ok())
+            .unwrap_or(default)
+    };
+    (var("COLUMNS", 80), var("LINES", 24))
+}
@@ -1,4 +1,4 @@
-use chrono::{NaiveDate, NaiveDateTime, NaiveTime, TimeZone, Utc};
+use chrono::{Datelike, Months, NaiveDate, NaiveDateTime, NaiveTime, SecondsFormat, TimeZone, Utc};
 use differential_dataflow::lattice::Lattice;
 use std::cmp;
 use std::fmt::{self, Debug, Display};
@@ -13,6 +13,16 @@ pub struct DateTime(chrono::DateTime<Utc>);
 #[repr(transparent)]
 pub struct Duration(chrono::Duration);
 
+// Calendar periods in UTC. Weeks begin on Monday and quarters in January,
+// April, July and October.
+#[derive(Copy, Clone, Debug)]
+pub enum Interval {
+    Day,
+    Week,
+    Month,
+    Quarter,
+}
+
 impl DateTime {
     pub fn new(date: NaiveDate, time: NaiveTime) -> Self {
         DateTime(Utc.from_utc_datetime(&NaiveDateTime::new(date, time)))
@@ -37,6 +47,44 @@ impl DateTime {
     pub fn from_timestamp(secs: i64, nanos: u32) -> Self {
         DateTime(chrono::DateTime::from_timestamp(secs, nanos).unwrap())
     }
+
+    pub fn to_rfc3339(&self) -> String {
+        self.0.to_rfc3339_opts(SecondsFormat::AutoSi, true)
+    }
+
+    #[must_use]
+    pub fn start_of_period(&self, interval: Interval) -> Self {
+        let date = self.0.date_naive();
+        let start = match interval {
+            Interval::Day => date,
+            Interval::Week => date.week(chrono::Weekday::Mon).first_day(),
+            Interval::Month => date.with_day(1).unwrap(),
+            Interval::Quarter => {
+                let month = date.month0() / 3 * 3 + 1;
+                NaiveDate::from_ymd_opt(date.year(), month, 1).unwrap()
+            }
+        };
+        DateTime::new(start, NaiveTime::MIN)
+    }
+
+    #[must_use]
+    pub fn months_before(&self, months: u32) -> Self {
+        DateTime(self.0.checked_sub_months(Months::new(months)).unwrap())
+    }
+
+    // The instant at which the period containing self ends, which is the
+    // start of the next period.
+    #[must_use]
+    pub fn end_of_period(&self, interval: Interval) -> Self {
+        let start = self.start_of_period(interval).0.date_naive();
+        let end = match interval {
+            Interval::Day => start.succ_opt().unwrap(),
+            Interval::Week => start + chrono::Duration::weeks(1),
+            Interval::Month => start + Months::new(1),
+            Interval::Quarter => start + Months::new(3),
+        };
+        DateTime::new(end, NaiveTime::MIN)
+    }
 }
 
 impl From<chrono::DateTime<Utc>> for DateTime {
@@ -0,0 +1,218 @@
+use crate::cratemap::CrateMap;
+use crate::query;
+use anyhow::Result;
+use cargo_tally::arena::Slice;
+use cargo_tally::id::{CrateId, TermId};
+use cargo_tally::matrix::Matrix;
+use cargo_tally::timestamp::DateTime;
+use cargo_tally::{Op, Predicate, Query};
+use std::cmp::{Ordering, Reverse};
+use std::io::{self, Write};
+
+// Fewest dependents at the start of the window for a crate to be ranked by its
+// relative growth or decline, so that a crate going from 1 to 3 dependents does
+// not top the list.
+const MIN_RELATIVE_BASE: u32 = 10;
+
+// One query for every crate, matching any version of it.
+pub(crate) fn queries(crates: &CrateMap) -> Result<(Vec<Query>, Vec<CrateId>)> {
+    let expr = Slice::new(&[Op::Term(TermId(0))]);
+    let mut queries = Vec::new();
+    let mut crate_ids = Vec::new();
+    for (crate_id, _name) in crates.iter() {
+        let predicate = Predicate {
+            crate_id,
+            req: None,
+            kinds: None,
+            feature: None,
+            default_features: None,
+        };
+        queries.push(Query {
+            id: query::query_id(queries.len())?,
+            terms: Slice::new(&[Slice::new(&[predicate])]),
+            expr,
+            depth: None,
+        });
+        crate_ids.push(crate_id);
+    }
+    Ok((queries, crate_ids))
+}
+
+// Number of dependents of every crate at the start and at the end of a window.
+struct Counts<'a> {
+    name: &'a str,
+    before: u32,
+    after: u32,
+}
+
+impl<'a> Counts<'a> {
+    fn change(&self) -> i64 {
+        i64::from(self.after) - i64::from(self.before)
+    }
+
+    fn relative_change(&self) -> f64 {
+        self.change() as f64 / f64::from(self.before)
+    }
+}
+
+fn counts<'a>(
+    results: &Matrix,
+    crate_ids: &[CrateId],
+    crates: &'a CrateMap,
+    since: DateTime,
+    at: DateTime,
+) -> Vec<Counts<'a>> {
+    crate_ids
+        .iter()
+        .enumerate()
+        .map(|(i, &crate_id)| Counts {
+            name: crates.name(crate_id).unwrap(),
+            before: value_at(results, i, since),
+            after: value_at(results, i, at),
+        })
+        .collect()
+}
+
+// Value of column i in effect at the instant `time`, not counting changes at
+// that instant.
+fn value_at(results: &Matrix, i: usize, time: DateTime) -> u32 {
+    results
+        .column(i)
+        .take_while(|&(timestamp, _value)| timestamp < time)
+        .last()
+        .map_or(0, |(_timestamp, value)| value)
+}
+
+// The `top` crates with the most dependents as of the instant `at`, and with
+// `since`, how many more or fewer they had than at that earlier instant.
+pub(crate) fn print(
+    out: &mut dyn Write,
+    results: &Matrix,
+    crate_ids: &[CrateId],
+    crates: &CrateMap,
+    top: usize,
+    at: DateTime,
+    since: Option<DateTime>,
+) -> io::Result<()> {
+    let mut ranking = counts(results, crate_ids, crates, since.unwrap_or(at), at);
+    ranking.retain(|counts| counts.after > 0);
+    ranking.sort_by_key(|counts| (Reverse(counts.after), counts.name));
+    ranking.truncate(top);
+
+    let rows: Vec<[String; 3]> = ranking
+        .iter()
+        .map(|counts| {
+            let change = match since {
+                Some(_) => format!("{:+}", counts.change()),
+                None => String::new(),
+            };
+            [counts.name.to_owned(), counts.after.to_string(), change]
+        })
+        .collect();
+    write_table(out, "", &rows)
+}
+
+// The `top` crates whose number of dependents grew the most between the
+// instants `since` and `at`, by count and relative to where they started, and
+// the same for the ones that declined the most.
+pub(crate) fn print_trending(
+    out: &mut dyn Write,
+    results: &Matrix,
+    crate_ids: &[CrateId],
+    crates: &CrateMap,
+    top: usize,
+    at: DateTime,
+    since: DateTime,
+) -> io::Result<()> {
+    let counts = counts(results, crate_ids, crates, since, at);
+    let by_change = |ordering: Ordering| {
+        let mut ranking: Vec<&Counts> = counts
+            .iter()
+            .filter(|counts| counts.change().cmp(&0) == ordering)
+            .collect();
+        ranking.sort_by_key(|counts| (Reverse(counts.change().abs()), counts.name));
+        ranking.truncate(top);
+        ranking
+    };
+    let by_relative_change = |ordering: Ordering| {
+        let mut ranking: Vec<&Counts> = counts
+            .iter()
+            .filter(|counts| {
+                counts.before >= MIN_RELATIVE_BASE && counts.change().cmp(&0) == ordering
+            })
+            .collect();
+        ranking.sort_by(|a, b| {
+            let a_relative = a.relative_change().abs();
+            let b_relative = b.relative_change().abs();
+            b_relative.total_cmp(&a_relative).then(a.name.cmp(b.name))
+        });
+        ranking.truncate(top);
+        ranking
+    };
+
+    let sections = [
+        ("grew the most", by_change(Ordering::Greater)),
+        (
+            "grew the most relative to their dependents at the start",
+            by_relative_change(Ordering::Greater),
+        ),
+        ("declined the most", by_change(Ordering::Less)),
+        (
+            "declined the most relative to their dependents at the start",
+            by_relative_change(Ordering::Less),
+        ),
+    ];
+    for (i, (heading, ranking)) in sections.into_iter().enumerate() {
+        if i > 0 {
+            writeln!(out)?;
+        }
+        writeln!(out, "{} ({})", heading, ranking.len())?;
+        let rows: Vec<[String; 3]> = ranking
+            .iter()
+            .map(|counts| {
+                let change = if counts.before == 0 {
+                    format!("{:+}", counts.change())
+                } else {
+                    format!(
+                        "{:+} ({:+.1}%)",
+                        counts.change(),
+                        counts.relative_change() * 100.0,
+                    )
+                };
+                [
+                    counts.name.to_owned(),
+                    format!("{} -> {}", counts.before, counts.after),
+                    change,
+                ]
+            })
+            .collect();
+        write_table(out, "    ", &rows)?;
+    }
+    Ok(())
+}
+
+// Numbered rows with the first column left aligned and the rest right aligned.
+fn write_table(out: &mut dyn Write, indent: &str, rows: &[[String; 3]]) -> io::Result<()> {
+    let rank_width = rows.len().to_string().len();
+    let mut widths = [0; 3];
+    for row in rows {
+        for (width, cell) in widths.iter_mut().zip(row) {
+            *width = (*width).max(cell.chars().count());
+        }
+    }
+    for (rank, [name, count, change]) in rows.iter().enumerate() {
+        let line = format!(
+            "{}{:>rank_width$}  {:name_width$}  {:>count_width$}  {:>change_width$}",
+            indent,
+            rank + 1,
+            name,
+            count,
+            change,
+            name_width = widths[0],
+            count_width = widths[1],
+            change_width = widths[2],
+        );
+        writeln!(out, "{}", line.trim_end())?;
+    }
+    Ok(())
+}
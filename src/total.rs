@@ -18,6 +18,11 @@ impl Total {
         Total { times }
     }
 
+    #[cfg(test)]
+    pub(crate) fn from_times(times: Vec<DateTime>) -> Self {
+        Total { times }
+    }
+
     pub(crate) fn eval(&self, time: DateTime) -> u32 {
         match self.times.binary_search(&time) {
             Ok(i) => 1 + i as u32,
@@ -0,0 +1,172 @@
+// Hand-built crates.io data for tests, shared by the integration tests and the
+// binary's unit tests.
+
+#![allow(dead_code)]
+
+use cargo_tally::arena::Slice;
+use cargo_tally::dependency::{DependencyKind, DependencyKinds, Optional};
+use cargo_tally::feature::{CrateFeature, DefaultFeatures, FeatureEnables, FeatureId};
+use cargo_tally::id::{CrateId, DependencyId, QueryId, TermId, VersionId};
+use cargo_tally::timestamp::{DateTime, Interval};
+use cargo_tally::version::Version;
+use cargo_tally::{Config, DbDump, Dependency, Op, Predicate, Query, Release};
+use chrono::{NaiveDate, NaiveTime};
+use std::collections::BTreeMap as Map;
+
+// Features of the same crate or of a dependency, by name.
+pub type CrateFeatures<'a> = &'a [(CrateId, &'a str)];
+
+// Every release is published on this date, and the tally is taken after it.
+pub fn published() -> DateTime {
+    DateTime::new(NaiveDate::from_ymd_opt(2022, 1, 1).unwrap(), NaiveTime::MIN)
+}
+
+#[derive(Default)]
+pub struct Builder {
+    pub db_dump: DbDump,
+}
+
+impl Builder {
+    // Version 1.0.0 of a crate, with the given features, each of which enables
+    // some features and weakly enables others.
+    pub fn release(
+        &mut self,
+        crate_id: CrateId,
+        features: &[(&str, CrateFeatures, CrateFeatures)],
+    ) -> VersionId {
+        self.version(crate_id, "1.0.0", features)
+    }
+
+    pub fn version(
+        &mut self,
+        crate_id: CrateId,
+        num: &str,
+        features: &[(&str, CrateFeatures, CrateFeatures)],
+    ) -> VersionId {
+        let id = VersionId(u32::try_from(self.db_dump.releases.len()).unwrap());
+        let features: Vec<FeatureEnables> = features
+            .iter()
+            .map(|(name, enables, weak_enables)| FeatureEnables {
+                id: self.db_dump.features.id(name),
+                enables: self.crate_features(enables),
+                weak_enables: self.crate_features(weak_enables),
+            })
+            .collect();
+        self.db_dump.releases.push(Release {
+            id,
+            crate_id,
+            num: Version(semver::Version::parse(num).unwrap()),
+            created_at: published(),
+            features: Slice::new(&features),
+        });
+        id
+    }
+
+    fn crate_features(&mut self, crate_features: CrateFeatures) -> Slice<CrateFeature> {
+        let crate_features: Vec<CrateFeature> = crate_features
+            .iter()
+            .map(|&(crate_id, feature)| CrateFeature {
+                crate_id,
+                feature_id: self.db_dump.features.id(feature),
+            })
+            .collect();
+        Slice::new(&crate_features)
+    }
+
+    // A normal dependency on ^1 of a crate, with default features, behind the
+    // named optional feature if any, and turning on the given features. The
+    // rest of the dependency can be changed through the returned reference.
+    pub fn dependency(
+        &mut self,
+        version_id: VersionId,
+        crate_id: CrateId,
+        optional: Option<&str>,
+        features: &[&str],
+    ) -> &mut Dependency {
+        let feature_id = match optional {
+            Some(name) => self.db_dump.features.id(name),
+            None => FeatureId::CRATE,
+        };
+        let features: Vec<FeatureId> = features
+            .iter()
+            .map(|feature| self.db_dump.features.id(feature))
+            .collect();
+        self.db_dump.dependencies.push(Dependency {
+            id: DependencyId(u32::try_from(self.db_dump.dependencies.len()).unwrap()),
+            version_id,
+            crate_id,
+            req: "^1".parse().unwrap(),
+            feature_id,
+            default_features: DefaultFeatures(true),
+            features: Slice::new(&features),
+            kind: DependencyKind::Normal,
+        });
+        self.db_dump.dependencies.last_mut().unwrap()
+    }
+}
+
+pub fn predicate(crate_id: CrateId) -> Predicate {
+    Predicate {
+        crate_id,
+        req: None,
+        kinds: None,
+        feature: None,
+        default_features: None,
+    }
+}
+
+// A query from its terms, each a group of predicates, and its expression.
+pub fn query(id: u32, terms: &[&[Predicate]], expr: &[Op]) -> Query {
+    let terms: Vec<Slice<Predicate>> = terms.iter().map(|term| Slice::new(term)).collect();
+    Query {
+        id: QueryId(id),
+        terms: Slice::new(&terms),
+        expr: Slice::new(expr),
+        depth: None,
+    }
+}
+
+// The query for a single crate, like `serde`.
+pub fn crate_query(crate_id: CrateId) -> Query {
+    query(0, &[&[predicate(crate_id)]], &[Op::Term(TermId(0))])
+}
+
+// The command line's defaults: every kind of dependency counts directly, and
+// only what gets built is followed transitively.
+pub fn config(transitive: bool) -> Config {
+    Config {
+        transitive,
+        direct_kinds: DependencyKinds::ALL,
+        transitive_kinds: DependencyKinds::BUILD,
+        optional: Optional::Include,
+        reverse: false,
+    }
+}
+
+// Crates counted in the query, as of after every release is published.
+pub fn counted(db_dump: DbDump, config: Config, query: Query) -> Vec<CrateId> {
+    let crate_ids: Map<VersionId, CrateId> = db_dump
+        .releases
+        .iter()
+        .map(|rel| (rel.id, rel.crate_id))
+        .collect();
+    let until = published().end_of_period(Interval::Day);
+    let changes = cargo_tally::dependents(db_dump, 1, config, &[query], until);
+
+    let mut counted = Map::new();
+    for ((version_id, _query_id), _timestamp, diff) in changes {
+        *counted.entry(crate_ids[&version_id]).or_insert(0) += diff;
+    }
+    counted
+        .into_iter()
+        .filter(|&(_crate_id, count)| count > 0)
+        .map(|(crate_id, _count)| crate_id)
+        .collect()
+}
+
+// The value of each query after every release is published.
+pub fn final_values(db_dump: DbDump, config: Config, queries: &[Query]) -> Vec<u32> {
+    let results = cargo_tally::run(db_dump, 1, config, queries);
+    let (_timestamp, row) = results.iter().next_back().unwrap();
+    row.into_iter().collect()
+}
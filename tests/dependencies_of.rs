@@ -0,0 +1,97 @@
+mod common;
+
+use crate::common::Builder;
+use cargo_tally::dependency::DependencyKind;
+use cargo_tally::id::CrateId;
+use cargo_tally::{Config, DbDump};
+
+const ROOT: CrateId = CrateId(1);
+const REQUIRED: CrateId = CrateId(2);
+const DEFAULT_OPTIONAL: CrateId = CrateId(3);
+const HEAVY: CrateId = CrateId(4);
+const HEAVY_DEPENDENCY: CrateId = CrateId(5);
+const TEST: CrateId = CrateId(6);
+const TEST_DEPENDENCY: CrateId = CrateId(7);
+const SHARED: CrateId = CrateId(8);
+
+// A root crate with:
+//
+//   - required: a plain dependency;
+//   - default-optional: an optional dependency turned on by default;
+//   - heavy: an optional dependency behind a feature that is not a default;
+//   - test: a dev-dependency.
+//
+// Each of heavy and test has a dependency of its own, and both required and
+// default-optional depend on shared, at different semver-incompatible versions.
+fn db_dump() -> DbDump {
+    let mut builder = Builder::default();
+
+    let root = builder.release(
+        ROOT,
+        &[
+            ("default", &[(ROOT, "default-optional")], &[]),
+            ("default-optional", &[], &[]),
+            ("heavy", &[], &[]),
+        ],
+    );
+    builder.dependency(root, REQUIRED, None, &[]);
+    builder.dependency(root, DEFAULT_OPTIONAL, Some("default-optional"), &[]);
+    builder.dependency(root, HEAVY, Some("heavy"), &[]);
+    builder.dependency(root, TEST, None, &[]).kind = DependencyKind::Dev;
+
+    let required = builder.release(REQUIRED, &[]);
+    builder.dependency(required, SHARED, None, &[]);
+
+    let default_optional = builder.release(DEFAULT_OPTIONAL, &[]);
+    builder.dependency(default_optional, SHARED, None, &[]).req = "^0.1".parse().unwrap();
+
+    let heavy = builder.release(HEAVY, &[]);
+    builder.dependency(heavy, HEAVY_DEPENDENCY, None, &[]);
+
+    let test = builder.release(TEST, &[]);
+    builder.dependency(test, TEST_DEPENDENCY, None, &[]);
+
+    for crate_id in [HEAVY_DEPENDENCY, TEST_DEPENDENCY] {
+        builder.release(crate_id, &[]);
+    }
+    builder.version(SHARED, "0.1.0", &[]);
+    builder.version(SHARED, "1.0.0", &[]);
+
+    builder.db_dump
+}
+
+fn config(transitive: bool) -> Config {
+    Config {
+        reverse: true,
+        ..common::config(transitive)
+    }
+}
+
+fn dependencies(transitive: bool) -> Vec<CrateId> {
+    common::counted(db_dump(), config(transitive), common::crate_query(ROOT))
+}
+
+#[test]
+fn test_default_features_only() {
+    let dependencies = dependencies(true);
+    assert!(dependencies.contains(&DEFAULT_OPTIONAL));
+    assert!(!dependencies.contains(&HEAVY));
+    assert!(!dependencies.contains(&HEAVY_DEPENDENCY));
+}
+
+#[test]
+fn test_dev_dependencies() {
+    assert!(dependencies(false).contains(&TEST));
+
+    let dependencies = dependencies(true);
+    assert!(!dependencies.contains(&TEST));
+    assert!(!dependencies.contains(&TEST_DEPENDENCY));
+}
+
+#[test]
+fn test_one_per_crate() {
+    let query = common::crate_query(ROOT);
+    let values = common::final_values(db_dump(), config(true), &[query]);
+    assert_eq!(values, [3]);
+    assert_eq!(dependencies(true), [REQUIRED, DEFAULT_OPTIONAL, SHARED]);
+}
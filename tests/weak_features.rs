@@ -0,0 +1,125 @@
+mod common;
+
+use crate::common::Builder;
+use cargo_tally::id::CrateId;
+use cargo_tally::DbDump;
+
+const LEAF: CrateId = CrateId(1);
+const SERDE: CrateId = CrateId(2);
+const WEAK_OPTIONAL: CrateId = CrateId(3);
+const WEAK_ENABLED: CrateId = CrateId(4);
+const WEAK_REQUIRED: CrateId = CrateId(5);
+const USES_WEAK_OPTIONAL: CrateId = CrateId(6);
+const USES_WEAK_ENABLED: CrateId = CrateId(7);
+const USES_WEAK_REQUIRED: CrateId = CrateId(8);
+const WEAK_COMBINED: CrateId = CrateId(9);
+const USES_WEAK_COMBINED: CrateId = CrateId(10);
+
+// A leaf crate, reachable only through serde's "std" feature, and three crates
+// that name serde's "std" feature weakly as `serde?/std`:
+//
+//   - weak-optional: serde is an optional dependency, and the feature with the
+//     weak enable does nothing to enable it;
+//   - weak-enabled: serde is an optional dependency enabled by the same feature
+//     that holds the weak enable;
+//   - weak-required: serde is not optional;
+//   - weak-combined: serde is an optional dependency enabled only by a feature
+//     of its own.
+//
+// plus one crate depending on each of those with that feature turned on, and
+// for weak-combined, also the feature that enables serde.
+fn db_dump() -> DbDump {
+    let mut builder = Builder::default();
+
+    builder.release(LEAF, &[]);
+
+    let serde = builder.release(SERDE, &[("std", &[(SERDE, "leaf")], &[])]);
+    builder.dependency(serde, LEAF, Some("leaf"), &[]);
+
+    let weak_optional = builder.release(
+        WEAK_OPTIONAL,
+        &[("std", &[], &[(SERDE, "std")]), ("serde", &[], &[])],
+    );
+    builder.dependency(weak_optional, SERDE, Some("serde"), &[]);
+
+    let weak_enabled = builder.release(
+        WEAK_ENABLED,
+        &[
+            ("std", &[(WEAK_ENABLED, "json")], &[(SERDE, "std")]),
+            ("json", &[(WEAK_ENABLED, "serde")], &[]),
+            ("serde", &[], &[]),
+        ],
+    );
+    builder.dependency(weak_enabled, SERDE, Some("serde"), &[]);
+
+    let weak_required = builder.release(WEAK_REQUIRED, &[("std", &[], &[(SERDE, "std")])]);
+    builder.dependency(weak_required, SERDE, None, &[]);
+
+    let weak_combined = builder.release(
+        WEAK_COMBINED,
+        &[("std", &[], &[(SERDE, "std")]), ("serde", &[], &[])],
+    );
+    builder.dependency(weak_combined, SERDE, Some("serde"), &[]);
+
+    for (dependent, dependency, features) in [
+        (USES_WEAK_OPTIONAL, WEAK_OPTIONAL, &["std"][..]),
+        (USES_WEAK_ENABLED, WEAK_ENABLED, &["std"]),
+        (USES_WEAK_REQUIRED, WEAK_REQUIRED, &["std"]),
+        (USES_WEAK_COMBINED, WEAK_COMBINED, &["serde", "std"]),
+    ] {
+        let version_id = builder.release(dependent, &[]);
+        builder.dependency(version_id, dependency, None, features);
+    }
+
+    builder.db_dump
+}
+
+// Transitive dependents of the leaf crate.
+fn dependents(db_dump: DbDump) -> Vec<CrateId> {
+    common::counted(db_dump, common::config(true), common::crate_query(LEAF))
+}
+
+#[test]
+fn test_weak_enable_of_disabled_dependency() {
+    let dependents = dependents(db_dump());
+    assert!(!dependents.contains(&WEAK_OPTIONAL));
+    assert!(!dependents.contains(&USES_WEAK_OPTIONAL));
+}
+
+#[test]
+fn test_weak_enable_of_enabled_dependency() {
+    let dependents = dependents(db_dump());
+    assert!(dependents.contains(&WEAK_ENABLED));
+    assert!(dependents.contains(&USES_WEAK_ENABLED));
+}
+
+#[test]
+fn test_weak_enable_of_required_dependency() {
+    let dependents = dependents(db_dump());
+    assert!(dependents.contains(&WEAK_REQUIRED));
+    assert!(dependents.contains(&USES_WEAK_REQUIRED));
+}
+
+#[test]
+fn test_weak_enable_of_dependency_enabled_alongside() {
+    let dependents = dependents(db_dump());
+    assert!(!dependents.contains(&WEAK_COMBINED));
+    assert!(dependents.contains(&USES_WEAK_COMBINED));
+}
+
+#[test]
+fn test_weak_enable_transitive_dependents() {
+    let dependents = dependents(db_dump());
+    assert_eq!(
+        dependents,
+        [
+            LEAF,
+            SERDE,
+            WEAK_ENABLED,
+            WEAK_REQUIRED,
+            USES_WEAK_ENABLED,
+            USES_WEAK_REQUIRED,
+            USES_WEAK_COMBINED,
+        ],
+    );
+}